@@ -0,0 +1,203 @@
+// src/axis.rs
+
+use crate::datetime;
+use crate::templating::AxisSpec;
+use std::error::Error;
+use std::ops::Range;
+
+// Smallest and largest finite value, or None when there are none
+pub fn data_extent(values: impl IntoIterator<Item = f64>) -> Option<(f64, f64)> {
+    values
+        .into_iter()
+        .filter(|value| value.is_finite())
+        .fold(None, |extent, value| match extent {
+            None => Some((value, value)),
+            Some((min, max)) => Some((min.min(value), max.max(value))),
+        })
+}
+
+// Widens the extent by `padding` (a fraction of its span) on both sides;
+// a single value gets a range around it so the axis is never empty
+pub fn padded(extent: (f64, f64), padding: f64) -> (f64, f64) {
+    let (min, max) = extent;
+    let span = max - min;
+    if span > 0.0 {
+        (min - span * padding, max + span * padding)
+    } else if min == 0.0 {
+        (-1.0, 1.0)
+    } else {
+        let margin = min.abs() * 0.1;
+        (min - margin, max + margin)
+    }
+}
+
+// A time extent as is, or a single timestamp widened by one day, hour, minute or second on
+// each side: the largest unit it falls on a whole number of, which `TimeAxis` then ticks in
+pub fn padded_time(extent: (f64, f64)) -> (f64, f64) {
+    if extent.1 > extent.0 {
+        return extent;
+    }
+    let timestamp = extent.0 as i64;
+    let unit = [86_400, 3600, 60, 1]
+        .into_iter()
+        .find(|unit| timestamp.rem_euclid(*unit) == 0)
+        .unwrap_or(1);
+    (extent.0 - unit as f64, extent.1 + unit as f64)
+}
+
+// Applies the user's min/max overrides to a computed range
+pub fn resolve(axis: &AxisSpec, computed: (f64, f64)) -> Result<Range<f64>, Box<dyn Error>> {
+    let min = axis.min.unwrap_or(computed.0);
+    let max = axis.max.unwrap_or(computed.1);
+    if min >= max {
+        return Err(format!("Axis minimum {} must be less than its maximum {}", min, max).into());
+    }
+    Ok(min..max)
+}
+
+// Tick spacing of 1, 2 or 5 times a power of ten giving roughly `target_ticks` intervals
+fn nice_step(span: f64, target_ticks: usize) -> f64 {
+    let raw = span / target_ticks.max(1) as f64;
+    let magnitude = 10f64.powf(raw.log10().floor());
+    let nice = match raw / magnitude {
+        f if f <= 1.0 => 1.0,
+        f if f <= 2.0 => 2.0,
+        f if f <= 5.0 => 5.0,
+        _ => 10.0,
+    };
+    nice * magnitude
+}
+
+// Expands the extent outward to whole tick steps and returns it with the number of tick labels
+pub fn nice(extent: (f64, f64), target_ticks: usize) -> ((f64, f64), usize) {
+    let span = extent.1 - extent.0;
+    if !span.is_finite() || span <= 0.0 {
+        return (extent, target_ticks + 1);
+    }
+    let step = nice_step(span, target_ticks);
+    let min = (extent.0 / step).floor() * step;
+    let max = (extent.1 / step).ceil() * step;
+    ((min, max), ((max - min) / step).round() as usize + 1)
+}
+
+// A range covering the extent, split into exactly `divisions` equal steps of 1, 2, 2.5 or 5 times a power of ten
+pub fn nice_divisions(extent: (f64, f64), divisions: usize) -> (f64, f64) {
+    let (low, high) = if extent.1 > extent.0 { extent } else { padded(extent, 0.0) };
+    let steps = divisions.max(1) as f64;
+    let mut magnitude = 10f64.powf(((high - low) / steps).log10().floor());
+    loop {
+        // Starting at a whole step may leave the top uncovered, so larger steps are tried in turn
+        for factor in [1.0, 2.0, 2.5, 5.0] {
+            let step = factor * magnitude;
+            let min = (low / step).floor() * step;
+            if min + step * steps >= high {
+                return (min, min + step * steps);
+            }
+        }
+        magnitude *= 10.0;
+    }
+}
+
+// Maps timestamps onto an axis counted in tick units (days, hours, ...) from a unit-aligned
+// origin, so the axis' round-number ticks fall on day, hour or minute boundaries
+#[derive(Clone, Copy)]
+pub struct TimeAxis {
+    origin: i64,
+    unit: i64,
+    label_format: &'static str,
+}
+
+impl TimeAxis {
+    pub fn new(extent: (f64, f64)) -> Self {
+        let (unit, label_format) = datetime::tick_unit((extent.1 - extent.0) as i64);
+        TimeAxis {
+            origin: (extent.0 as i64).div_euclid(unit) * unit,
+            unit,
+            label_format,
+        }
+    }
+
+    pub fn to_axis(self, timestamp: f64) -> f64 {
+        (timestamp - self.origin as f64) / self.unit as f64
+    }
+
+    pub fn label(&self, value: f64) -> String {
+        let timestamp = self.origin + (value * self.unit as f64).round() as i64;
+        datetime::format_timestamp(timestamp, self.label_format)
+    }
+
+    // At most one label per unit, so no two ticks share the same date text
+    pub fn label_count(&self, range: &Range<f64>) -> usize {
+        ((range.end - range.start).floor() as usize + 1).clamp(2, 10)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_extent_of_the_finite_values() {
+        assert_eq!(data_extent([3.0, f64::NAN, -1.0, f64::INFINITY, 2.0]), Some((-1.0, 3.0)));
+        assert_eq!(data_extent([f64::NAN]), None);
+        assert_eq!(data_extent(Vec::new()), None);
+    }
+
+    #[test]
+    fn pads_ranges_and_widens_single_values() {
+        assert_eq!(padded((0.0, 10.0), 0.1), (-1.0, 11.0));
+        assert_eq!(padded((5.0, 5.0), 0.1), (4.5, 5.5));
+        assert_eq!(padded((-5.0, -5.0), 0.1), (-5.5, -4.5));
+        assert_eq!(padded((0.0, 0.0), 0.1), (-1.0, 1.0));
+    }
+
+    #[test]
+    fn pads_a_single_timestamp_by_the_unit_it_falls_on() {
+        let day = (datetime::days_from_civil(2023, 10, 29) * 86_400) as f64;
+        assert_eq!(padded_time((day, day + 60.0)), (day, day + 60.0));
+        assert_eq!(padded_time((day, day)), (day - 86_400.0, day + 86_400.0));
+        assert_eq!(padded_time((day + 7200.0, day + 7200.0)), (day + 3600.0, day + 10_800.0));
+        assert_eq!(padded_time((day + 61.0, day + 61.0)), (day + 60.0, day + 62.0));
+
+        let axis = TimeAxis::new(padded_time((day, day)));
+        assert_eq!(axis.label(axis.to_axis(day)), "2023-10-29");
+    }
+
+    #[test]
+    fn lets_the_user_override_either_end() {
+        let axis = |min, max| AxisSpec { label: None, min, max };
+        assert_eq!(resolve(&axis(None, None), (1.0, 2.0)).unwrap(), 1.0..2.0);
+        assert_eq!(resolve(&axis(Some(0.0), None), (1.0, 2.0)).unwrap(), 0.0..2.0);
+        assert_eq!(resolve(&axis(None, Some(5.0)), (1.0, 2.0)).unwrap(), 1.0..5.0);
+        assert!(resolve(&axis(Some(3.0), None), (1.0, 2.0)).is_err());
+        assert!(resolve(&axis(Some(2.0), Some(2.0)), (1.0, 2.0)).is_err());
+    }
+
+    #[test]
+    fn rounds_ranges_out_to_whole_tick_steps() {
+        assert_eq!(nice((0.3, 9.2), 10), ((0.0, 10.0), 11));
+        assert_eq!(nice((-13.0, 47.0), 5), ((-20.0, 60.0), 5));
+        assert_eq!(nice((0.0, 0.7), 4), ((0.0, 0.8), 5));
+        assert_eq!(nice((2.0, 2.0), 5), ((2.0, 2.0), 6));
+    }
+
+    #[test]
+    fn splits_ranges_into_the_requested_number_of_steps() {
+        assert_eq!(nice_divisions((0.0, 9.0), 5), (0.0, 10.0));
+        assert_eq!(nice_divisions((3.0, 97.0), 4), (0.0, 100.0));
+        assert_eq!(nice_divisions((-7.0, 3.0), 5), (-7.5, 5.0));
+        let (low, high) = nice_divisions((5.0, 5.0), 5);
+        assert!(low <= 4.5 && high >= 5.5, "{}..{}", low, high);
+    }
+
+    #[test]
+    fn counts_time_axes_in_whole_units_from_an_aligned_origin() {
+        let start = datetime::days_from_civil(2023, 10, 1) * 86_400;
+        let axis = TimeAxis::new(((start + 3600) as f64, (start + 10 * 86_400) as f64));
+        assert_eq!(axis.to_axis(start as f64), 0.0);
+        assert_eq!(axis.to_axis((start + 3 * 86_400 + 43_200) as f64), 3.5);
+        assert_eq!(axis.label(2.0), "2023-10-03");
+        assert_eq!(axis.label_count(&(0.0..10.04)), 10);
+        assert_eq!(axis.label_count(&(0.0..0.5)), 2);
+    }
+}
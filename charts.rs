@@ -0,0 +1,148 @@
+// src/charts.rs
+
+use crate::templating::{ChartKind, Template};
+use std::error::Error;
+
+// A kind of chart: reads its data from the template's inputs, checks it, and draws it
+pub trait Chart: Default {
+    type Data;
+
+    fn load(&self, template: &Template) -> Result<Self::Data, Box<dyn Error>>;
+
+    // Problems the reader cannot see on its own, e.g. too few labels to draw
+    fn validate(&self, _data: &Self::Data, _template: &Template) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn render(&self, data: &Self::Data, template: &Template) -> Result<(), Box<dyn Error>>;
+}
+
+fn run<C: Chart>(template: &Template) -> Result<(), Box<dyn Error>> {
+    let chart = C::default();
+    let data = chart.load(template)?;
+    chart.validate(&data, template)?;
+    chart.render(&data, template)
+}
+
+// What the menu, the command line and spec files need to know about one chart kind
+pub struct ChartEntry {
+    pub kind: ChartKind,
+    // The first name is shown to users; the others are accepted as well
+    pub names: &'static [&'static str],
+    pub description: &'static str,
+    // Menu text describing the expected CSV columns
+    pub columns: &'static str,
+    pub default_size: (u32, u32),
+    // Asks the menu questions specific to this chart, after the input file
+    pub prompt: fn(&mut Template),
+    pub render: fn(&Template) -> Result<(), Box<dyn Error>>,
+}
+
+impl ChartEntry {
+    pub fn name(&self) -> &'static str {
+        self.names[0]
+    }
+}
+
+const BAR: ChartEntry = ChartEntry {
+    kind: ChartKind::Bar,
+    names: &["bar"],
+    description: "Bar chart, grouped or stacked, from one CSV file",
+    columns: "'Category', then one value column per series, or a grouping column",
+    default_size: crate::BAR_CHART_SIZE,
+    prompt: crate::prompt_bar_options,
+    render: run::<crate::BarChart>,
+};
+
+const SCATTER: ChartEntry = ChartEntry {
+    kind: ChartKind::Scatter,
+    names: &["scatter"],
+    description: "Scatter plot with one series per CSV file, or per --group value",
+    columns: "'X', 'Y' in one CSV per series, or a grouping column",
+    default_size: crate::SCATTER_PLOT_SIZE,
+    prompt: crate::prompt_scatter_options,
+    render: run::<crate::ScatterChart>,
+};
+
+const PIE: ChartEntry = ChartEntry {
+    kind: ChartKind::Pie,
+    names: &["pie"],
+    description: "Pie chart from one CSV file",
+    columns: "'Category', 'Percentage'",
+    default_size: crate::PIE_CHART_SIZE,
+    prompt: crate::prompt_pie_options,
+    render: run::<crate::PieChart>,
+};
+
+const LINE: ChartEntry = ChartEntry {
+    kind: ChartKind::Line,
+    names: &["line", "area"],
+    description: "Line, area or stacked area chart from one CSV file",
+    columns: "'Date', then one or more value columns",
+    default_size: crate::LINE_AREA_CHART_SIZE,
+    prompt: crate::prompt_line_options,
+    render: run::<crate::LineAreaChart>,
+};
+
+const RADAR: ChartEntry = ChartEntry {
+    kind: ChartKind::Radar,
+    names: &["radar"],
+    description: "Radar chart with one polygon per value column, or per --group value",
+    columns: "'Label', then one value column per series",
+    default_size: crate::RADAR_CHART_SIZE,
+    prompt: crate::prompt_radar_options,
+    render: run::<crate::RadarChart>,
+};
+
+const HISTOGRAM: ChartEntry = ChartEntry {
+    kind: ChartKind::Histogram,
+    names: &["histogram", "hist"],
+    description: "Histogram of one or more numeric columns, or of one column per --group value",
+    columns: "one or more numeric columns to bin",
+    default_size: crate::HISTOGRAM_SIZE,
+    prompt: crate::prompt_histogram_options,
+    render: run::<crate::HistogramChart>,
+};
+
+// Every chart kind, in menu order
+pub const CHARTS: &[ChartEntry] = &[BAR, SCATTER, PIE, LINE, RADAR, HISTOGRAM];
+
+pub fn find(name: &str) -> Option<&'static ChartEntry> {
+    let name = name.trim().to_lowercase();
+    CHARTS.iter().find(|entry| entry.names.contains(&name.as_str()))
+}
+
+// Matches every kind, so a chart kind without an entry does not compile
+pub fn entry(kind: ChartKind) -> &'static ChartEntry {
+    match kind {
+        ChartKind::Bar => &BAR,
+        ChartKind::Scatter => &SCATTER,
+        ChartKind::Pie => &PIE,
+        ChartKind::Line => &LINE,
+        ChartKind::Radar => &RADAR,
+        ChartKind::Histogram => &HISTOGRAM,
+    }
+}
+
+// "bar, scatter, pie, ..." for error messages
+pub fn names() -> String {
+    CHARTS.iter().map(ChartEntry::name).collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_every_entry_once_under_its_names() {
+        for chart in CHARTS {
+            assert_eq!(entry(chart.kind).name(), chart.name());
+            assert_eq!(CHARTS.iter().filter(|other| other.kind == chart.kind).count(), 1);
+            for name in chart.names {
+                assert_eq!(find(&name.to_uppercase()).map(|found| found.kind), Some(chart.kind));
+            }
+        }
+        assert!(find("spec").is_none());
+        assert_eq!(names(), "bar, scatter, pie, line, radar, histogram");
+    }
+}
@@ -0,0 +1,492 @@
+// src/cli.rs
+
+use crate::charts;
+use crate::datetime;
+use crate::numbers::NumberStyle;
+use crate::palette::Palette;
+use crate::templating::{
+    self, Binning, ChartKind, OutputFormat, PieAnnotation, RadarAxisLimit, RadarGrid, RadarScale, SortOrder,
+    Template,
+};
+use std::error::Error;
+
+const USAGE_HEADER: &str = "\
+Usage: <program> <chart> [options]
+       <program> spec <chart.toml> [options]
+
+Charts:";
+
+const USAGE_OPTIONS: &str = "\
+Options:
+  -i, --input <file.csv>    Input CSV file (repeat for one scatter series per file)
+  -o, --output <file>       Output image file (.png or .svg)
+      --format <png|svg>    Output format (default: from the output file extension)
+  -t, --title <text>        Chart title
+      --columns <a,b,...>   Columns to plot, in the order the chart expects
+      --group <column>      Bar, scatter, radar or histogram column whose values split the data into series
+      --mode <mode>         Line chart: line, area or stacked (default: area)
+                            Bar chart: grouped, stacked or percent (default: grouped)
+      --date-format <fmt>   Line chart date layout, e.g. '%d/%m/%Y' (default: ISO-8601)
+      --grid <polygon|circle>
+                            Radar chart ring shape (default: polygon)
+      --radar-scale <global|per-axis>
+                            Radar chart: one value scale for all labels, or one per label (default: global)
+      --axis-limit <label=min:max>
+                            Fixed value range for one radar label (repeatable)
+      --annotate <percent|value|both|none>
+                            Pie chart slice annotations (default: percent)
+      --legend              Pie chart legend beside the slices
+      --donut <fraction>    Draw the pie as a donut with this inner radius, e.g. 0.5
+      --center-text <text>  Donut center text; {total} is the sum of the values (default: the total)
+      --explode <a,b,...>   Pie slice labels to pull out of the pie
+      --category <column>   Bar chart category column (default: first column)
+      --value <column>      Bar chart value column (default: the first other column); use --columns for several
+      --horizontal          Bar chart with the categories down the y axis
+      --sort <asc|desc>     Order the bars by value (default: file order)
+      --top <n>             Keep the n largest bars and add up the rest as 'Other'
+      --bins <n|sturges|fd> Histogram bin count, or the Sturges or Freedman-Diaconis rule (default: sturges)
+      --bin-width <width>   Histogram bins of a fixed width instead
+      --density             Draw a density curve over each histogram distribution
+      --value-labels        Print the value of each bar, line vertex or scatter point
+      --number-style <plain|percent|si>
+                            How value labels write numbers: 0.25 as 25%, 1500 as 1.5k (default: plain)
+      --decimals <n>        Decimals in value labels (default: up to 2)
+      --thousands           Separate thousands in value labels: 1,234,567
+      --palette <name>      Colors: categorical, colorblind, sequential or random
+      --seed <n>            Varies the palette's color for each name, the same on every run
+      --x-min, --x-max <n>  X axis limits, numbers or ISO-8601 dates (default: fitted to the data)
+      --y-min, --y-max <n>  Y axis limits, or the radar value range (default: fitted to the data)
+      --width <pixels>      Canvas width
+      --height <pixels>     Canvas height
+      --scale <factor>      Scale the canvas, fonts, margins and markers, e.g. 2 for slides or print
+      --dpi <dots>          Same as --scale, in dots per inch (96 dpi is a scale of 1)
+  -h, --help                Show this message
+
+Bar, pie and scatter CSV files may add a 'color' column of #RRGGBB, #RRGGBBAA or CSS color names.
+Options given with 'spec' override the values in the spec file.
+Run without arguments to use the interactive menu.";
+
+// The chart list comes from the registry, so every chart kind is listed
+pub fn usage() -> String {
+    let mut usage = format!("{}\n", USAGE_HEADER);
+    for entry in charts::CHARTS {
+        usage += &format!("  {:<9} {}\n", entry.name(), entry.description);
+    }
+    usage += &format!("  {:<9} {}\n\n", "spec", "Render the chart described by a TOML spec file");
+    usage + USAGE_OPTIONS
+}
+
+pub fn is_help(args: &[String]) -> bool {
+    args.iter().any(|arg| arg == "-h" || arg == "--help")
+}
+
+pub fn parse_args(args: &[String]) -> Result<Template, Box<dyn Error>> {
+    let mut iter = args.iter();
+    let chart = iter.next().ok_or("Missing chart type")?.to_lowercase();
+
+    let mut template = if chart == "spec" {
+        let path = iter.next().ok_or("Missing spec file (spec <chart.toml>)")?;
+        templating::load_template(path)?
+    } else {
+        Template::new(ChartKind::parse(&chart).ok_or_else(|| {
+            format!("Unknown chart type '{}'. Expected one of: {}, spec", chart, charts::names())
+        })?)
+    };
+
+    let mut inputs = Vec::new();
+    let mut category = None;
+    let mut value_column = None;
+    while let Some(flag) = iter.next() {
+        let mut value = || {
+            iter.next()
+                .ok_or_else(|| format!("Missing value for '{}'", flag))
+        };
+
+        match flag.as_str() {
+            "-i" | "--input" => inputs.push(value()?.to_string()),
+            "-o" | "--output" => template.output = value()?.to_string(),
+            "--format" => {
+                let name = value()?;
+                template.format = Some(
+                    OutputFormat::parse(name)
+                        .ok_or_else(|| format!("Unknown format '{}'. Expected 'png' or 'svg'", name))?,
+                );
+            }
+            "-t" | "--title" => template.title = value()?.to_string(),
+            "--columns" => template.columns = split_list(value()?),
+            "--group" => template.group_column = Some(value()?.to_string()),
+            "--mode" => template.set_mode(value()?)?,
+            "--date-format" => template.date_format = Some(value()?.to_string()),
+            "--grid" => {
+                let name = value()?;
+                template.radar_grid = RadarGrid::parse(name)
+                    .ok_or_else(|| format!("Unknown grid '{}'. Expected 'polygon' or 'circle'", name))?;
+            }
+            "--radar-scale" => {
+                let name = value()?;
+                template.radar_scale = RadarScale::parse(name)
+                    .ok_or_else(|| format!("Unknown radar scale '{}'. Expected 'global' or 'per-axis'", name))?;
+            }
+            "--axis-limit" => template.radar_limits.push(parse_radar_limit(value()?)?),
+            "--annotate" => {
+                let name = value()?;
+                template.pie_annotation = PieAnnotation::parse(name).ok_or_else(|| {
+                    format!("Unknown annotation '{}'. Expected 'percent', 'value', 'both' or 'none'", name)
+                })?;
+            }
+            "--legend" => template.legend = true,
+            "--donut" => {
+                let text = value()?;
+                template.donut = match text.parse::<f64>() {
+                    Ok(fraction) if fraction > 0.0 && fraction < 1.0 => Some(fraction),
+                    _ => {
+                        return Err(
+                            format!("Invalid value '{}' for '--donut'. Expected a number between 0 and 1.", text).into()
+                        )
+                    }
+                };
+            }
+            "--center-text" => template.center_text = Some(value()?.to_string()),
+            "--explode" => template.explode = split_list(value()?),
+            "--category" => category = Some(value()?.to_string()),
+            "--value" => value_column = Some(value()?.to_string()),
+            "--horizontal" => template.horizontal = true,
+            "--sort" => {
+                let name = value()?;
+                template.sort = Some(
+                    SortOrder::parse(name)
+                        .ok_or_else(|| format!("Unknown sort order '{}'. Expected 'asc' or 'desc'", name))?,
+                );
+            }
+            "--top" => template.top = Some(parse_dimension(flag, value()?)? as usize),
+            "--bins" => {
+                let name = value()?;
+                template.binning = Binning::parse(name).ok_or_else(|| {
+                    format!("Invalid value '{}' for '--bins'. Expected a bin count, 'sturges' or 'fd'", name)
+                })?;
+            }
+            "--bin-width" => {
+                let text = value()?;
+                template.binning = match text.parse::<f64>() {
+                    Ok(width) if width.is_finite() && width > 0.0 => Binning::Width(width),
+                    _ => {
+                        return Err(format!("Invalid value '{}' for '--bin-width'. Expected a positive number.", text).into())
+                    }
+                };
+            }
+            "--density" => template.density = true,
+            "--value-labels" => template.value_labels = true,
+            "--number-style" => {
+                let name = value()?;
+                template.number_format.style = NumberStyle::parse(name).ok_or_else(|| {
+                    format!("Unknown number style '{}'. Expected 'plain', 'percent' or 'si'", name)
+                })?;
+            }
+            "--decimals" => {
+                let text = value()?;
+                template.number_format.decimals = match text.parse::<usize>() {
+                    Ok(decimals) if decimals <= 10 => Some(decimals),
+                    _ => {
+                        return Err(
+                            format!("Invalid value '{}' for '--decimals'. Expected an integer from 0 to 10.", text).into()
+                        )
+                    }
+                };
+            }
+            "--thousands" => template.number_format.thousands = true,
+            "--palette" => {
+                let name = value()?;
+                template.palette = Some(Palette::parse(name).ok_or_else(|| {
+                    format!("Unknown palette '{}'. Expected categorical, colorblind, sequential or random", name)
+                })?);
+            }
+            "--seed" => {
+                let text = value()?;
+                template.seed = Some(
+                    text.parse::<u64>()
+                        .map_err(|_| format!("Invalid value '{}' for '--seed'. Expected a non-negative integer.", text))?,
+                );
+            }
+            "--x-min" => template.x_axis.min = Some(parse_axis_limit(flag, value()?)?),
+            "--x-max" => template.x_axis.max = Some(parse_axis_limit(flag, value()?)?),
+            "--y-min" => template.y_axis.min = Some(parse_axis_limit(flag, value()?)?),
+            "--y-max" => template.y_axis.max = Some(parse_axis_limit(flag, value()?)?),
+            "--width" => template.width = Some(parse_dimension(flag, value()?)?),
+            "--height" => template.height = Some(parse_dimension(flag, value()?)?),
+            "--scale" => template.scale = parse_scale(flag, value()?)?,
+            "--dpi" => template.scale = parse_scale(flag, value()?)? / 96.0,
+            _ => return Err(format!("Unknown option '{}'", flag).into()),
+        }
+    }
+
+    if !inputs.is_empty() {
+        template.inputs = inputs;
+    }
+    if category.is_some() || value_column.is_some() {
+        let current = |index: usize| template.columns.get(index).cloned();
+        let category = category.or_else(|| current(0));
+        let value_column = value_column.or_else(|| current(1));
+        // An empty category keeps the chart's default category column
+        template.columns = match value_column {
+            Some(value_column) => vec![category.unwrap_or_default(), value_column],
+            None => category.into_iter().collect(),
+        };
+    }
+    if template.inputs.is_empty() {
+        return Err("At least one input file is required (--input <file.csv>)".into());
+    }
+    if template.output.is_empty() {
+        return Err("An output file is required (--output <file.png|file.svg>)".into());
+    }
+
+    Ok(template)
+}
+
+// Comma-separated names, e.g. "a, b,c"
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+fn parse_dimension(flag: &str, value: &str) -> Result<u32, Box<dyn Error>> {
+    match value.parse::<u32>() {
+        Ok(pixels) if pixels > 0 => Ok(pixels),
+        _ => Err(format!("Invalid value '{}' for '{}'. Expected a positive integer.", value, flag).into()),
+    }
+}
+
+fn parse_scale(flag: &str, value: &str) -> Result<f64, Box<dyn Error>> {
+    match value.parse::<f64>() {
+        Ok(scale) if scale.is_finite() && scale > 0.0 => Ok(scale),
+        _ => Err(format!("Invalid value '{}' for '{}'. Expected a positive number.", value, flag).into()),
+    }
+}
+
+// Axis limits are numbers, or ISO-8601 dates for date axes (as seconds since the epoch)
+fn parse_axis_limit(flag: &str, value: &str) -> Result<f64, Box<dyn Error>> {
+    match value.parse::<f64>() {
+        Ok(number) if number.is_finite() => Ok(number),
+        _ => datetime::parse_iso8601(value)
+            .map(|timestamp| timestamp as f64)
+            .ok_or_else(|| format!("Invalid value '{}' for '{}'. Expected a number or a date.", value, flag).into()),
+    }
+}
+
+// `<label>=<min>:<max>`, e.g. "Speed=0:10"
+fn parse_radar_limit(value: &str) -> Result<RadarAxisLimit, Box<dyn Error>> {
+    let invalid = || format!("Invalid value '{}' for '--axis-limit'. Expected <label>=<min>:<max>", value);
+    let (label, range) = value.rsplit_once('=').ok_or_else(invalid)?;
+    let (min, max) = range.split_once(':').ok_or_else(invalid)?;
+    let (min, max) = match (min.trim().parse::<f64>(), max.trim().parse::<f64>()) {
+        (Ok(min), Ok(max)) if min < max => (min, max),
+        _ => return Err(invalid().into()),
+    };
+    Ok(RadarAxisLimit {
+        label: label.trim().to_string(),
+        min,
+        max,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Template, Box<dyn Error>> {
+        parse_args(&args.split_whitespace().map(str::to_string).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn reads_the_chart_and_its_options() {
+        let template = parse("Bar -i sales.csv -o out.png -t Sales --width 800").unwrap();
+        assert_eq!(template.visualization_type, ChartKind::Bar);
+        assert_eq!(template.inputs, ["sales.csv"]);
+        assert_eq!(template.output, "out.png");
+        assert_eq!(template.title, "Sales");
+        assert_eq!(template.size((640, 480)), (800, 480));
+
+        let template = parse("scatter --input a.csv -i b.csv --output out.png --height 300").unwrap();
+        assert_eq!(template.inputs, ["a.csv", "b.csv"]);
+        assert_eq!(template.size((600, 400)), (600, 300));
+    }
+
+    #[test]
+    fn maps_the_bar_category_and_value_columns() {
+        let columns = |args: &str| parse(&format!("bar -i in.csv -o out.png {}", args)).unwrap().columns;
+        assert_eq!(columns("--category Team --value Wins"), ["Team", "Wins"]);
+        assert_eq!(columns("--category Team"), ["Team"]);
+        assert_eq!(columns("--value Wins"), ["", "Wins"]);
+        assert!(columns("").is_empty());
+    }
+
+    #[test]
+    fn reads_the_output_format() {
+        assert_eq!(parse("pie -i in.csv -o out.svg --format SVG").unwrap().format, Some(OutputFormat::Svg));
+        assert_eq!(parse("pie -i in.csv -o out.svg").unwrap().format, None);
+        assert!(parse("pie -i in.csv -o out.svg --format gif").is_err());
+    }
+
+    #[test]
+    fn reads_the_scatter_group_and_columns() {
+        let template = parse("scatter -i in.csv -o out.png --group Kind --columns x,,y").unwrap();
+        assert_eq!(template.group_column.as_deref(), Some("Kind"));
+        assert_eq!(template.columns, ["x", "y"]);
+    }
+
+    #[test]
+    fn reads_dates_for_the_x_axis() {
+        let template = parse("line -i in.csv -o out.png --date-format %d/%m/%Y --x-min 2023-10-29").unwrap();
+        assert_eq!(template.date_format.as_deref(), Some("%d/%m/%Y"));
+        assert_eq!(template.x_axis.min, Some(datetime::days_from_civil(2023, 10, 29) as f64 * 86_400.0));
+        assert!(parse("line -i in.csv -o out.png --x-min 29/10/2023").is_err());
+    }
+
+    #[test]
+    fn reads_the_line_mode() {
+        assert_eq!(parse("line -i in.csv -o out.png --mode lines").unwrap().line_mode, templating::LineMode::Line);
+        assert!(parse("line -i in.csv -o out.png --mode bars").is_err());
+    }
+
+    #[test]
+    fn reads_the_bar_order_and_orientation() {
+        let template = parse("bar -i in.csv -o out.png --horizontal --sort DESC --top 5").unwrap();
+        assert!(template.horizontal);
+        assert_eq!((template.sort, template.top), (Some(SortOrder::Descending), Some(5)));
+        assert!(parse("bar -i in.csv -o out.png --sort up").is_err());
+        assert!(parse("bar -i in.csv -o out.png --top 0").is_err());
+    }
+
+    #[test]
+    fn reads_the_value_label_format() {
+        let template = parse("bar -i in.csv -o out.png --value-labels --number-style si --decimals 1 --thousands").unwrap();
+        assert!(template.value_labels);
+        let expected = crate::numbers::NumberFormat {
+            style: NumberStyle::Si,
+            decimals: Some(1),
+            thousands: true,
+        };
+        assert_eq!(template.number_format, expected);
+        assert!(parse("bar -i in.csv -o out.png --number-style roman").is_err());
+        assert!(parse("bar -i in.csv -o out.png --decimals 11").is_err());
+    }
+
+    #[test]
+    fn reads_the_histogram_bins() {
+        let template = parse("hist -i in.csv -o out.png --bins fd --density").unwrap();
+        assert_eq!(template.visualization_type, ChartKind::Histogram);
+        assert_eq!(template.binning, Binning::FreedmanDiaconis);
+        assert!(template.density);
+        assert_eq!(parse("histogram -i in.csv -o out.png --bins 12").unwrap().binning, Binning::Count(12));
+        assert_eq!(parse("histogram -i in.csv -o out.png --bin-width 2.5").unwrap().binning, Binning::Width(2.5));
+        assert!(parse("histogram -i in.csv -o out.png --bins 0").is_err());
+        assert!(parse("histogram -i in.csv -o out.png --bin-width -1").is_err());
+    }
+
+    #[test]
+    fn reads_the_bar_mode_and_group() {
+        let template = parse("bar -i in.csv -o out.png --mode stacked --group Team").unwrap();
+        assert_eq!(template.bar_mode, templating::BarMode::Stacked);
+        assert_eq!(template.group_column.as_deref(), Some("Team"));
+        assert!(parse("bar -i in.csv -o out.png --mode area").is_err());
+    }
+
+    #[test]
+    fn sets_the_radar_value_scale() {
+        let template = parse("radar -i in.csv -o out.png --radar-scale per-axis --axis-limit Top=Speed=0:10").unwrap();
+        assert_eq!(template.radar_scale, RadarScale::PerAxis);
+        assert_eq!(template.radar_limits[0].label, "Top=Speed");
+        assert_eq!((template.radar_limits[0].min, template.radar_limits[0].max), (0.0, 10.0));
+        assert!(parse("radar -i in.csv -o out.png --radar-scale 2").is_err());
+        assert!(parse("radar -i in.csv -o out.png --axis-limit Speed=10:0").is_err());
+    }
+
+    #[test]
+    fn sets_the_canvas_size_and_scale() {
+        let template = parse("bar -i in.csv -o out.png --width 800 --height 600 --scale 2").unwrap();
+        assert_eq!((template.width, template.height), (Some(800), Some(600)));
+        assert_eq!(template.scale, 2.0);
+        assert_eq!(template.pixel_size((100, 50)), (1600, 1200));
+        assert_eq!(parse("radar -i in.csv -o out.png --dpi 144").unwrap().scale, 1.5);
+        for scale in ["0", "-1", "inf", "global"] {
+            assert!(parse(&format!("bar -i in.csv -o out.png --scale {}", scale)).is_err());
+        }
+    }
+
+    #[test]
+    fn reads_the_pie_annotation_and_legend() {
+        let template = parse("pie -i in.csv -o out.png --annotate Both --legend").unwrap();
+        assert_eq!(template.pie_annotation, PieAnnotation::Both);
+        assert!(template.legend);
+        assert!(!parse("pie -i in.csv -o out.png").unwrap().legend);
+        assert!(parse("pie -i in.csv -o out.png --annotate labels").is_err());
+    }
+
+    #[test]
+    fn reads_the_donut_and_exploded_slices() {
+        let template = parse("pie -i in.csv -o out.png --donut 0.5 --center-text Total --explode a,b").unwrap();
+        assert_eq!(template.donut, Some(0.5));
+        assert_eq!(template.center_text.as_deref(), Some("Total"));
+        assert_eq!(template.explode, ["a", "b"]);
+        for fraction in ["0", "1", "half"] {
+            assert!(parse(&format!("pie -i in.csv -o out.png --donut {}", fraction)).is_err(), "{}", fraction);
+        }
+    }
+
+    #[test]
+    fn reads_the_palette_and_seed() {
+        let template = parse("bar -i in.csv -o out.png --palette ColorBlind --seed 42").unwrap();
+        assert_eq!(template.palette, Some(Palette::Colorblind));
+        assert_eq!(template.seed, Some(42));
+        assert!(parse("bar -i in.csv -o out.png --palette rainbow").is_err());
+        assert!(parse("bar -i in.csv -o out.png --seed -1").is_err());
+    }
+
+    #[test]
+    fn overrides_the_axis_limits() {
+        let template = parse("scatter -i a.csv -i b.csv -o out.png --x-min -5 --y-max 2.5e2").unwrap();
+        assert_eq!((template.x_axis.min, template.x_axis.max), (Some(-5.0), None));
+        assert_eq!((template.y_axis.min, template.y_axis.max), (None, Some(250.0)));
+        for limit in ["nan", "inf", "ten"] {
+            assert!(parse(&format!("scatter -i a.csv -o out.png --x-max {}", limit)).is_err(), "{}", limit);
+        }
+    }
+
+    #[test]
+    fn rejects_incomplete_or_unknown_options() {
+        for args in [
+            "",
+            "heatmap -i in.csv -o out.png",
+            "spec",
+            "bar -o out.png",
+            "bar -i in.csv",
+            "bar -i in.csv -o",
+            "bar -i in.csv -o out.png --colour red",
+            "bar -i in.csv -o out.png --width 0",
+            "bar -i in.csv -o out.png --height tall",
+        ] {
+            assert!(parse(args).is_err(), "{}", args);
+        }
+    }
+
+    #[test]
+    fn lists_every_registered_chart() {
+        let usage = usage();
+        for entry in charts::CHARTS {
+            assert!(usage.contains(&format!("  {:<9} {}\n", entry.name(), entry.description)), "{}", entry.name());
+        }
+        let error = parse("donut -i in.csv -o out.png").unwrap_err().to_string();
+        assert!(error.ends_with(&format!("Expected one of: {}, spec", charts::names())), "{}", error);
+        assert_eq!(parse("AREA -i in.csv -o out.png").unwrap().visualization_type, ChartKind::Line);
+    }
+
+    #[test]
+    fn spots_a_help_flag_anywhere() {
+        let args = |text: &str| text.split_whitespace().map(str::to_string).collect::<Vec<_>>();
+        assert!(is_help(&args("--help")));
+        assert!(is_help(&args("bar -i in.csv -h")));
+        assert!(!is_help(&args("bar -i help.csv")));
+    }
+}
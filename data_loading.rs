@@ -1,34 +1,406 @@
 // src/data_loading.rs
 
-use csv;
+use crate::datetime;
+use crate::error::ChartError;
 use std::collections::HashMap;
 use std::error::Error;
+use std::fmt;
 use std::fs::File;
+use std::rc::Rc;
 
 pub struct DataRow {
-    pub headers: Vec<String>,
+    pub headers: Rc<Vec<String>>,
     pub values: HashMap<String, String>,
 }
 
 pub fn load_csv(file_path: &str) -> Result<Vec<DataRow>, Box<dyn Error>> {
-    let mut rdr = csv::Reader::from_reader(File::open(file_path)?);
-    let headers = rdr
-        .headers()?
-        .iter()
-        .map(|s| s.to_string())
-        .collect::<Vec<String>>();
+    let (headers, records) = read_records(file_path)?;
+    let headers = Rc::new(headers);
 
     let mut data = Vec::new();
-    for result in rdr.records() {
-        let record = result?;
+    for record in records {
         let mut row_data = HashMap::new();
-        for (header, value) in headers.iter().zip(record.iter()) {
-            row_data.insert(header.clone(), value.to_string());
+        for (header, value) in headers.iter().zip(record) {
+            row_data.insert(header.clone(), value);
         }
         data.push(DataRow {
-            headers: headers.clone(),
+            headers: Rc::clone(&headers),
             values: row_data,
         });
     }
     Ok(data)
 }
+
+// The trimmed header, then every record as its cells
+type Records = (Vec<String>, Vec<Vec<String>>);
+
+fn read_records(file_path: &str) -> Result<Records, Box<dyn Error>> {
+    let file = File::open(file_path).map_err(|source| ChartError::Io {
+        path: file_path.to_string(),
+        source,
+    })?;
+    let mut rdr = csv::Reader::from_reader(file);
+    let headers = rdr
+        .headers()
+        .map_err(|e| ChartError::csv(file_path, e))?
+        .iter()
+        .map(|s| s.trim().to_string())
+        .collect::<Vec<String>>();
+
+    let mut records = Vec::new();
+    for result in rdr.records() {
+        let record = result.map_err(|e| ChartError::csv(file_path, e))?;
+        records.push(record.iter().map(|s| s.to_string()).collect());
+    }
+    Ok((headers, records))
+}
+
+// Finds `name` among the headers (exact match first, then case-insensitive) and returns the header as written
+pub fn find_column<'a>(headers: &'a [String], name: &str, file_path: &str) -> Result<&'a str, Box<dyn Error>> {
+    headers
+        .iter()
+        .find(|header| header.as_str() == name)
+        .or_else(|| headers.iter().find(|header| header.eq_ignore_ascii_case(name)))
+        .map(|header| header.as_str())
+        .ok_or_else(|| {
+            ChartError::MissingColumn {
+                path: file_path.to_string(),
+                column: name.to_string(),
+                available: headers.to_vec(),
+            }
+            .into()
+        })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColumnType {
+    Integer,
+    Float,
+    DateTime,
+    Categorical,
+    Boolean,
+}
+
+impl fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ColumnType::Integer => "an integer",
+            ColumnType::Float => "a number",
+            ColumnType::DateTime => "a date/time",
+            ColumnType::Categorical => "text",
+            ColumnType::Boolean => "a boolean",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+pub enum ColumnData {
+    Integer(Vec<i64>),
+    Float(Vec<f64>),
+    // Seconds since the Unix epoch
+    DateTime(Vec<i64>),
+    Categorical(Vec<String>),
+    Boolean(Vec<bool>),
+}
+
+pub struct Column {
+    pub name: String,
+    pub data: ColumnData,
+    // The cells as written in the CSV, e.g. "1.50" or "2023-10-29 14:30"
+    cells: Vec<String>,
+}
+
+impl Column {
+    pub fn column_type(&self) -> ColumnType {
+        match self.data {
+            ColumnData::Integer(_) => ColumnType::Integer,
+            ColumnData::Float(_) => ColumnType::Float,
+            ColumnData::DateTime(_) => ColumnType::DateTime,
+            ColumnData::Categorical(_) => ColumnType::Categorical,
+            ColumnData::Boolean(_) => ColumnType::Boolean,
+        }
+    }
+
+    // Cell text as it appears in the CSV, used for labels and error messages;
+    // booleans are written as true or false
+    pub fn text(&self, row: usize) -> String {
+        match &self.data {
+            ColumnData::Categorical(values) => values[row].clone(),
+            ColumnData::Boolean(values) => values[row].to_string(),
+            _ => self.cells[row].clone(),
+        }
+    }
+}
+
+fn parse_bool(text: &str) -> Option<bool> {
+    match text.trim().to_lowercase().as_str() {
+        "true" | "yes" => Some(true),
+        "false" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+fn convert(values: &[String], column_type: ColumnType) -> Result<ColumnData, usize> {
+    fn all<T>(values: &[String], parse: impl Fn(&str) -> Option<T>) -> Result<Vec<T>, usize> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(row, value)| parse(value.trim()).ok_or(row))
+            .collect()
+    }
+
+    Ok(match column_type {
+        ColumnType::Integer => ColumnData::Integer(all(values, |s| s.parse().ok())?),
+        // "nan" and "inf" parse as floats, but are not values a chart can place
+        ColumnType::Float => ColumnData::Float(all(values, |s| s.parse().ok().filter(|v: &f64| v.is_finite()))?),
+        ColumnType::DateTime => ColumnData::DateTime(all(values, datetime::parse_iso8601)?),
+        ColumnType::Boolean => ColumnData::Boolean(all(values, parse_bool)?),
+        ColumnType::Categorical => ColumnData::Categorical(values.to_vec()),
+    })
+}
+
+// Only literal true/false columns are inferred as booleans, so that yes/no answers keep their
+// text as categories; a schema can still declare a yes/no column boolean
+fn infer(values: &[String]) -> ColumnData {
+    if values.is_empty() {
+        return ColumnData::Categorical(Vec::new());
+    }
+    let literal_bools = values
+        .iter()
+        .all(|value| matches!(value.trim().to_lowercase().as_str(), "true" | "false"));
+    [ColumnType::Integer, ColumnType::Float, ColumnType::DateTime]
+        .iter()
+        .find_map(|column_type| convert(values, *column_type).ok())
+        .or_else(|| literal_bools.then(|| convert(values, ColumnType::Boolean).ok()).flatten())
+        .unwrap_or_else(|| ColumnData::Categorical(values.to_vec()))
+}
+
+// A CSV file loaded column by column, each column with a single type
+pub struct Table {
+    pub file_path: String,
+    pub columns: Vec<Column>,
+    pub row_count: usize,
+}
+
+// Loads a CSV file, inferring the type of every column
+pub fn load_table(file_path: &str) -> Result<Table, Box<dyn Error>> {
+    load_table_with_schema(file_path, &[])
+}
+
+// Loads a CSV file; columns named in `schema` must hold the given type, the rest are inferred
+pub fn load_table_with_schema(
+    file_path: &str,
+    schema: &[(&str, ColumnType)],
+) -> Result<Table, Box<dyn Error>> {
+    let (headers, records) = read_records(file_path)?;
+
+    for (name, _) in schema {
+        find_column(&headers, name, file_path)?;
+    }
+
+    let mut columns = Vec::new();
+    for (index, name) in headers.iter().enumerate() {
+        let values: Vec<String> = records.iter().map(|record| record[index].clone()).collect();
+        let declared = schema
+            .iter()
+            .find(|(column, _)| column.eq_ignore_ascii_case(name))
+            .map(|(_, column_type)| *column_type);
+
+        let data = match declared {
+            Some(column_type) => convert(&values, column_type).map_err(|row| {
+                ChartError::conversion(file_path, row + 1, name, &values[row], column_type.to_string())
+            })?,
+            None => infer(&values),
+        };
+        columns.push(Column {
+            name: name.clone(),
+            data,
+            cells: values,
+        });
+    }
+
+    Ok(Table {
+        file_path: file_path.to_string(),
+        columns,
+        row_count: records.len(),
+    })
+}
+
+impl Table {
+    pub fn headers(&self) -> Vec<String> {
+        self.columns.iter().map(|column| column.name.clone()).collect()
+    }
+
+    pub fn column_index(&self, name: &str) -> Result<usize, Box<dyn Error>> {
+        let headers = self.headers();
+        let found = find_column(&headers, name, &self.file_path)?;
+        Ok(headers.iter().position(|header| header == found).unwrap())
+    }
+
+    pub fn column_at(&self, index: usize) -> Result<&Column, Box<dyn Error>> {
+        self.columns.get(index).ok_or_else(|| {
+            ChartError::invalid(
+                &self.file_path,
+                format!("has {} columns, but column {} was requested", self.columns.len(), index + 1),
+            )
+            .into()
+        })
+    }
+
+    // Reports the first cell of `column` that cannot be read as `expected`
+    fn type_error(&self, column: &Column, expected: ColumnType) -> Box<dyn Error> {
+        if self.row_count == 0 {
+            return Box::new(ChartError::EmptyData {
+                path: self.file_path.clone(),
+                reason: None,
+            });
+        }
+        let (row, value) = (0..self.row_count)
+            .map(|row| (row, column.text(row)))
+            .find(|(_, value)| convert(std::slice::from_ref(value), expected).is_err())
+            .unwrap_or((0, column.text(0)));
+        Box::new(ChartError::conversion(&self.file_path, row + 1, &column.name, &value, expected.to_string()))
+    }
+
+    pub fn floats(&self, index: usize) -> Result<Vec<f64>, Box<dyn Error>> {
+        let column = self.column_at(index)?;
+        match &column.data {
+            ColumnData::Integer(values) => Ok(values.iter().map(|v| *v as f64).collect()),
+            ColumnData::Float(values) => Ok(values.clone()),
+            _ => Err(self.type_error(column, ColumnType::Float)),
+        }
+    }
+
+    pub fn integers(&self, index: usize) -> Result<Vec<i64>, Box<dyn Error>> {
+        let column = self.column_at(index)?;
+        match &column.data {
+            ColumnData::Integer(values) => Ok(values.clone()),
+            _ => Err(self.type_error(column, ColumnType::Integer)),
+        }
+    }
+
+    pub fn datetimes(&self, index: usize) -> Result<Vec<i64>, Box<dyn Error>> {
+        let column = self.column_at(index)?;
+        match &column.data {
+            ColumnData::DateTime(values) => Ok(values.clone()),
+            _ => Err(self.type_error(column, ColumnType::DateTime)),
+        }
+    }
+
+    // Reads dates laid out as `format` (see `datetime::parse_with_format`), even in columns
+    // that were inferred as ISO-8601 dates, since e.g. 2023-02-03 may be the 2nd of March
+    pub fn datetimes_with_format(&self, index: usize, format: &str) -> Result<Vec<i64>, Box<dyn Error>> {
+        let column = self.column_at(index)?;
+        (0..self.row_count)
+            .map(|row| {
+                let value = column.text(row);
+                datetime::parse_with_format(&value, format).ok_or_else(|| {
+                    let expected = format!("a date like '{}'", format);
+                    Box::<dyn Error>::from(ChartError::conversion(&self.file_path, row + 1, &column.name, &value, expected))
+                })
+            })
+            .collect()
+    }
+
+    // Any column can be read as text
+    pub fn strings(&self, index: usize) -> Result<Vec<String>, Box<dyn Error>> {
+        let column = self.column_at(index)?;
+        Ok((0..self.row_count).map(|row| column.text(row)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Writes `contents` to a scratch file and loads it with `schema`
+    fn load(name: &str, contents: &str, schema: &[(&str, ColumnType)]) -> Result<Table, Box<dyn Error>> {
+        let path = std::env::temp_dir().join(format!("data_loading_{}_{}.csv", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        let table = load_table_with_schema(path.to_str().unwrap(), schema);
+        std::fs::remove_file(&path).unwrap();
+        table
+    }
+
+    fn types(table: &Table) -> Vec<ColumnType> {
+        table.columns.iter().map(Column::column_type).collect()
+    }
+
+    #[test]
+    fn finds_columns_by_exact_then_case_insensitive_name() {
+        let headers: Vec<String> = ["value", "Value", "Name"].iter().map(|header| header.to_string()).collect();
+        assert_eq!(find_column(&headers, "Value", "in.csv").unwrap(), "Value");
+        assert_eq!(find_column(&headers, "name", "in.csv").unwrap(), "Name");
+        let message = find_column(&headers, "age", "in.csv").unwrap_err().to_string();
+        assert_eq!(message, "Column 'age' not found in 'in.csv'. Available columns: value, Value, Name");
+    }
+
+    #[test]
+    fn infers_one_type_per_column() {
+        let table = load(
+            "infer",
+            "count,price,when,name,done\n1,1.5,2023-10-29,a,true\n2,2,2023-10-30 14:30,b,false\n",
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            types(&table),
+            [
+                ColumnType::Integer,
+                ColumnType::Float,
+                ColumnType::DateTime,
+                ColumnType::Categorical,
+                ColumnType::Boolean
+            ]
+        );
+        assert_eq!(table.row_count, 2);
+        assert_eq!(table.integers(0).unwrap(), [1, 2]);
+        assert_eq!(table.floats(1).unwrap(), [1.5, 2.0]);
+    }
+
+    #[test]
+    fn keeps_cell_text_of_typed_columns() {
+        let table = load("text", "answer,price,when\nyes,1.50,2023-10-29 14:30\nNo,2,2023-10-30\n", &[]).unwrap();
+        assert_eq!(types(&table), [ColumnType::Categorical, ColumnType::Float, ColumnType::DateTime]);
+        assert_eq!(table.strings(0).unwrap(), ["yes", "No"]);
+        assert_eq!(table.strings(1).unwrap(), ["1.50", "2"]);
+        assert_eq!(table.strings(2).unwrap(), ["2023-10-29 14:30", "2023-10-30"]);
+
+        let table = load("declared", "answer\nyes\nNo\n", &[("answer", ColumnType::Boolean)]).unwrap();
+        assert_eq!(table.strings(0).unwrap(), ["true", "false"]);
+    }
+
+    #[test]
+    fn does_not_read_nan_or_inf_as_numbers() {
+        let table = load("nonfinite", "a,b\n1,nan\n2,inf\n", &[]).unwrap();
+        assert_eq!(types(&table), [ColumnType::Integer, ColumnType::Categorical]);
+        let error = table.floats(1).unwrap_err().to_string();
+        assert!(error.contains("row 1, column 'b'"), "{}", error);
+
+        let error = load("nonfinite_schema", "a\n1\n-inf\n", &[("a", ColumnType::Float)]).err().unwrap();
+        assert!(error.to_string().contains("row 2, column 'a'"), "{}", error);
+    }
+
+    #[test]
+    fn reads_dates_with_the_given_format() {
+        // Inferred as ISO dates, but the file writes year-day-month
+        let table = load("format", "when\n2023-02-03\n2023-13-01\n", &[]).unwrap();
+        assert_eq!(
+            table.datetimes_with_format(0, "%Y-%d-%m").unwrap(),
+            [datetime::days_from_civil(2023, 3, 2) * 86400, datetime::days_from_civil(2023, 1, 13) * 86400]
+        );
+
+        let table = load("format_iso", "when\n2023-02-03\n", &[]).unwrap();
+        assert_eq!(table.datetimes_with_format(0, "%Y-%d-%m").unwrap(), [datetime::days_from_civil(2023, 3, 2) * 86400]);
+        let error = table.datetimes_with_format(0, "%d/%m/%Y").unwrap_err().to_string();
+        assert!(error.contains("row 1, column 'when'"), "{}", error);
+    }
+
+    #[test]
+    fn reports_the_cell_that_breaks_a_declared_type() {
+        let error = load("schema", "n\n1\nx\n", &[("n", ColumnType::Integer)]).err().unwrap();
+        assert!(error.to_string().contains("row 2, column 'n': expected an integer, found 'x'"), "{}", error);
+        let error = load("missing", "n\n1\n", &[("m", ColumnType::Integer)]).err().unwrap();
+        assert!(matches!(error.downcast_ref(), Some(ChartError::MissingColumn { .. })));
+    }
+}
@@ -0,0 +1,307 @@
+// src/datetime.rs
+
+// Dates and times are handled as seconds since 1970-01-01T00:00:00 UTC
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+// Days since the Unix epoch for a proleptic Gregorian date
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+// (year, month, day) for a number of days since the Unix epoch
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        _ if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+        _ => 28,
+    }
+}
+
+pub fn to_timestamp(year: i64, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> Option<i64> {
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    Some(days_from_civil(year, month, day) * SECONDS_PER_DAY + (hour * 3600 + minute * 60 + second) as i64)
+}
+
+// Reads exactly `len` ASCII digits
+fn take_digits(text: &mut &str, len: usize) -> Option<u32> {
+    let digits = text.get(..len)?;
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    *text = &text[len..];
+    digits.parse().ok()
+}
+
+fn take_char(text: &mut &str, expected: &[char]) -> Option<char> {
+    let c = text.chars().next().filter(|c| expected.contains(c))?;
+    *text = &text[c.len_utf8()..];
+    Some(c)
+}
+
+// Parses ISO-8601 dates and timestamps such as `2023-10-29`, `2023/10/29`,
+// `2023-10-29 14:30`, `2023-10-29T14:30:15.250Z` or `2023-10-29T14:30:15+07:00`
+pub fn parse_iso8601(text: &str) -> Option<i64> {
+    let mut rest = text.trim();
+    let year = take_digits(&mut rest, 4)?;
+    let separator = take_char(&mut rest, &['-', '/'])?;
+    let month = take_digits(&mut rest, 2)?;
+    take_char(&mut rest, &[separator])?;
+    let day = take_digits(&mut rest, 2)?;
+
+    if rest.is_empty() {
+        return to_timestamp(year as i64, month, day, 0, 0, 0);
+    }
+
+    take_char(&mut rest, &['T', ' '])?;
+    let hour = take_digits(&mut rest, 2)?;
+    take_char(&mut rest, &[':'])?;
+    let minute = take_digits(&mut rest, 2)?;
+    let second = if take_char(&mut rest, &[':']).is_some() {
+        take_digits(&mut rest, 2)?
+    } else {
+        0
+    };
+
+    // Fractional seconds are accepted but dropped
+    if take_char(&mut rest, &['.', ',']).is_some() {
+        let fraction_len = rest.bytes().take_while(|b| b.is_ascii_digit()).count();
+        if fraction_len == 0 {
+            return None;
+        }
+        rest = &rest[fraction_len..];
+    }
+
+    let offset = match take_char(&mut rest, &['Z', 'z', '+', '-']) {
+        None | Some('Z') | Some('z') => 0,
+        Some(sign) => {
+            let hours = take_digits(&mut rest, 2)? as i64;
+            take_char(&mut rest, &[':']);
+            let minutes = take_digits(&mut rest, 2)? as i64;
+            let offset = hours * 3600 + minutes * 60;
+            if sign == '+' { offset } else { -offset }
+        }
+    };
+
+    if !rest.is_empty() {
+        return None;
+    }
+    Some(to_timestamp(year as i64, month, day, hour, minute, second)? - offset)
+}
+
+const MONTH_NAMES: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+// Reads between one and `max_len` ASCII digits
+fn take_number(text: &mut &str, max_len: usize) -> Option<u32> {
+    let len = text.bytes().take(max_len).take_while(|b| b.is_ascii_digit()).count();
+    if len == 0 {
+        return None;
+    }
+    let number = text[..len].parse().ok()?;
+    *text = &text[len..];
+    Some(number)
+}
+
+fn take_month_name(text: &mut &str) -> Option<u32> {
+    let name = text.get(..3)?;
+    let month = MONTH_NAMES
+        .iter()
+        .position(|month| month.eq_ignore_ascii_case(name))?;
+    *text = &text[3..];
+    // Accept full names such as "October" as well as "Oct"
+    let full_len = text.bytes().take_while(|b| b.is_ascii_alphabetic()).count();
+    *text = &text[full_len..];
+    Some(month as u32 + 1)
+}
+
+// Parses `text` laid out as `format`, a strftime-style pattern using
+// %Y, %y, %m, %d, %b, %H, %M, %S and %%; e.g. "%d/%m/%Y" or "%b %d %Y %H:%M"
+pub fn parse_with_format(text: &str, format: &str) -> Option<i64> {
+    let (mut year, mut month, mut day) = (1970, 1, 1);
+    let (mut hour, mut minute, mut second) = (0, 0, 0);
+    let mut rest = text.trim();
+    let mut pattern = format.chars();
+
+    while let Some(c) = pattern.next() {
+        if c != '%' {
+            if c == ' ' {
+                rest = rest.trim_start();
+            } else {
+                take_char(&mut rest, &[c])?;
+            }
+            continue;
+        }
+        match pattern.next()? {
+            'Y' => year = take_number(&mut rest, 4)? as i64,
+            // Two-digit years follow the POSIX rule: 69-99 are 1900s, 00-68 are 2000s
+            'y' => {
+                let short = take_digits(&mut rest, 2)? as i64;
+                year = if short >= 69 { 1900 + short } else { 2000 + short };
+            }
+            'm' => month = take_number(&mut rest, 2)?,
+            'd' => day = take_number(&mut rest, 2)?,
+            'b' => month = take_month_name(&mut rest)?,
+            'H' => hour = take_number(&mut rest, 2)?,
+            'M' => minute = take_number(&mut rest, 2)?,
+            'S' => second = take_number(&mut rest, 2)?,
+            '%' => {
+                take_char(&mut rest, &['%'])?;
+            }
+            _ => return None,
+        }
+    }
+
+    if !rest.is_empty() {
+        return None;
+    }
+    to_timestamp(year, month, day, hour, minute, second)
+}
+
+// Formats a timestamp with the same directives `parse_with_format` understands
+pub fn format_timestamp(timestamp: i64, format: &str) -> String {
+    let (year, month, day) = civil_from_days(timestamp.div_euclid(SECONDS_PER_DAY));
+    let seconds_of_day = timestamp.rem_euclid(SECONDS_PER_DAY);
+    let (hour, minute, second) = (seconds_of_day / 3600, seconds_of_day % 3600 / 60, seconds_of_day % 60);
+
+    let mut output = String::new();
+    let mut pattern = format.chars();
+    while let Some(c) = pattern.next() {
+        if c != '%' {
+            output.push(c);
+            continue;
+        }
+        match pattern.next() {
+            Some('Y') => output.push_str(&year.to_string()),
+            Some('y') => output.push_str(&format!("{:02}", year.rem_euclid(100))),
+            Some('m') => output.push_str(&format!("{:02}", month)),
+            Some('d') => output.push_str(&format!("{:02}", day)),
+            Some('b') => output.push_str(MONTH_NAMES[month as usize - 1]),
+            Some('H') => output.push_str(&format!("{:02}", hour)),
+            Some('M') => output.push_str(&format!("{:02}", minute)),
+            Some('S') => output.push_str(&format!("{:02}", second)),
+            Some('%') => output.push('%'),
+            Some(other) => {
+                output.push('%');
+                output.push(other);
+            }
+            None => output.push('%'),
+        }
+    }
+    output
+}
+
+// Tick unit for a time axis spanning `span` seconds: the unit in seconds and its label format
+pub fn tick_unit(span: i64) -> (i64, &'static str) {
+    if span >= 2 * 365 * SECONDS_PER_DAY {
+        (SECONDS_PER_DAY, "%Y-%m")
+    } else if span >= 2 * SECONDS_PER_DAY {
+        (SECONDS_PER_DAY, "%Y-%m-%d")
+    } else if span >= 2 * 3600 {
+        (3600, "%m-%d %H:%M")
+    } else if span >= 120 {
+        (60, "%H:%M")
+    } else {
+        (1, "%H:%M:%S")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_between_days_and_dates() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+        for days in [-800_000, -1, 0, 11_016, 11_017, 19_700, 2_000_000] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn parses_iso8601_dates_and_offsets() {
+        let day = days_from_civil(2023, 10, 29) * SECONDS_PER_DAY;
+        assert_eq!(parse_iso8601("2023-10-29"), Some(day));
+        assert_eq!(parse_iso8601(" 2023/10/29 "), Some(day));
+        assert_eq!(parse_iso8601("2023-10-29 14:30"), Some(day + 14 * 3600 + 30 * 60));
+        assert_eq!(parse_iso8601("2023-10-29T14:30:15.250Z"), Some(day + 14 * 3600 + 30 * 60 + 15));
+        assert_eq!(parse_iso8601("2023-10-29T14:30:15+07:00"), Some(day + 7 * 3600 + 30 * 60 + 15));
+        for text in ["2023-10-29-", "2023-13-01", "2023-02-29", "2023-10/29", "29/10/2023", "2023-10-29T25:00"] {
+            assert_eq!(parse_iso8601(text), None, "{}", text);
+        }
+        assert_eq!(parse_iso8601("2024-02-29"), Some(days_from_civil(2024, 2, 29) * SECONDS_PER_DAY));
+    }
+
+    #[test]
+    fn round_trips_through_a_format() {
+        for format in ["%d/%m/%Y", "%Y-%m-%d %H:%M:%S", "%b %d %Y %H:%M", "%y%m%d", "100%% on %d.%m.%Y"] {
+            for timestamp in [0, 951_782_400, 1_698_589_815 / 60 * 60, -86_400 * 365] {
+                let text = format_timestamp(timestamp, format);
+                let parsed = parse_with_format(&text, format).unwrap_or_else(|| panic!("{} as {}", text, format));
+                // Formats without seconds or times of day round down to what they show
+                assert_eq!(format_timestamp(parsed, format), text);
+            }
+        }
+        assert_eq!(format_timestamp(1_698_589_815, "%Y-%m-%dT%H:%M:%S"), "2023-10-29T14:30:15");
+        assert_eq!(
+            parse_with_format("Oct 29 2023 14:30", "%b %d %Y %H:%M"),
+            parse_with_format("October 29 2023 14:30", "%b %d %Y %H:%M")
+        );
+    }
+
+    #[test]
+    fn reads_two_digit_years_around_1969() {
+        let year_of = |text: &str| parse_with_format(text, "%d/%m/%y").map(|t| civil_from_days(t / SECONDS_PER_DAY).0);
+        assert_eq!(year_of("01/01/00"), Some(2000));
+        assert_eq!(year_of("01/01/68"), Some(2068));
+        assert_eq!(year_of("01/01/69"), Some(1969));
+        assert_eq!(year_of("01/01/99"), Some(1999));
+        assert_eq!(year_of("01/01/2023"), None);
+    }
+
+    #[test]
+    fn rejects_text_that_does_not_fit_the_format() {
+        assert_eq!(parse_with_format("2023-10-29", "%d/%m/%Y"), None);
+        assert_eq!(parse_with_format("29/10/2023 extra", "%d/%m/%Y"), None);
+        assert_eq!(parse_with_format("31/04/2023", "%d/%m/%Y"), None);
+        assert_eq!(parse_with_format("29/10/2023", "%d/%m/%Q"), None);
+    }
+
+    #[test]
+    fn picks_tick_units_for_the_span() {
+        assert_eq!(tick_unit(3 * 365 * SECONDS_PER_DAY), (SECONDS_PER_DAY, "%Y-%m"));
+        assert_eq!(tick_unit(10 * SECONDS_PER_DAY), (SECONDS_PER_DAY, "%Y-%m-%d"));
+        assert_eq!(tick_unit(6 * 3600), (3600, "%m-%d %H:%M"));
+        assert_eq!(tick_unit(600), (60, "%H:%M"));
+        assert_eq!(tick_unit(30), (1, "%H:%M:%S"));
+    }
+}
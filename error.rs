@@ -0,0 +1,199 @@
+// src/error.rs
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+// Everything that can go wrong between reading a CSV file and writing the chart.
+// Rows are 1-based data rows, not counting the header line.
+#[derive(Debug)]
+pub enum ChartError {
+    // The file could not be opened or read
+    Io { path: String, source: io::Error },
+    // The file is not valid CSV, e.g. a row with more fields than the header
+    Csv { path: String, row: Option<usize>, message: String },
+    MissingColumn { path: String, column: String, available: Vec<String> },
+    // A cell that cannot be read as what its column should hold
+    Conversion {
+        path: String,
+        row: usize,
+        column: String,
+        value: String,
+        expected: String,
+    },
+    // The file has no data rows, or nothing the chart can draw
+    EmptyData { path: String, reason: Option<String> },
+    // The file can be read, but its columns or values do not fit the chart
+    InvalidData {
+        path: String,
+        row: Option<usize>,
+        column: Option<String>,
+        message: String,
+    },
+    // The drawing backend failed, e.g. the output file could not be written
+    Render { path: String, message: String },
+    // The output file name does not fit the output format
+    Output { path: String, message: String },
+    // The chart was not told what to read, or where to draw
+    NoInput,
+    NoOutput,
+}
+
+impl ChartError {
+    pub fn csv(path: &str, error: csv::Error) -> ChartError {
+        // The header is record 0, so a record number is also the 1-based data row
+        let row = error.position().map(|position| position.record() as usize);
+        let message = error.to_string();
+        match error.into_kind() {
+            csv::ErrorKind::Io(source) => ChartError::Io {
+                path: path.to_string(),
+                source,
+            },
+            csv::ErrorKind::UnequalLengths { expected_len, len, .. } => ChartError::Csv {
+                path: path.to_string(),
+                row,
+                message: format!("expected {} fields like the header, found {}", expected_len, len),
+            },
+            _ => ChartError::Csv {
+                path: path.to_string(),
+                row,
+                message,
+            },
+        }
+    }
+
+    // `row` is the 1-based data row
+    pub fn conversion(path: &str, row: usize, column: &str, value: &str, expected: impl Into<String>) -> ChartError {
+        ChartError::Conversion {
+            path: path.to_string(),
+            row,
+            column: column.to_string(),
+            value: value.to_string(),
+            expected: expected.into(),
+        }
+    }
+
+    // A problem with the file as a whole rather than with one cell
+    pub fn invalid(path: &str, message: impl Into<String>) -> ChartError {
+        ChartError::InvalidData {
+            path: path.to_string(),
+            row: None,
+            column: None,
+            message: message.into(),
+        }
+    }
+
+    // A problem with how one column is used, e.g. a grouping column that cannot apply
+    pub fn invalid_column(path: &str, column: &str, message: impl Into<String>) -> ChartError {
+        ChartError::InvalidData {
+            path: path.to_string(),
+            row: None,
+            column: Some(column.to_string()),
+            message: message.into(),
+        }
+    }
+
+    // Drawing errors are reported as render failures, unless they already describe a data problem
+    pub fn render(path: &str, error: Box<dyn Error>) -> Box<dyn Error> {
+        if error.is::<ChartError>() {
+            return error;
+        }
+        Box::new(ChartError::Render {
+            path: path.to_string(),
+            message: error.to_string(),
+        })
+    }
+}
+
+// "'data.csv' row 3, column 'Value'", leaving out the parts that are unknown
+fn location(path: &str, row: Option<usize>, column: Option<&str>) -> String {
+    let mut text = format!("'{}'", path);
+    if let Some(row) = row {
+        text += &format!(" row {}", row);
+    }
+    if let Some(column) = column {
+        text += &format!("{} column '{}'", if row.is_some() { "," } else { "" }, column);
+    }
+    text
+}
+
+impl fmt::Display for ChartError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChartError::Io { path, source } => write!(f, "Could not read '{}': {}", path, source),
+            ChartError::Csv { path, row, message } => {
+                write!(f, "{}: invalid CSV, {}", location(path, *row, None), message)
+            }
+            ChartError::MissingColumn {
+                path,
+                column,
+                available,
+            } => write!(
+                f,
+                "Column '{}' not found in '{}'. Available columns: {}",
+                column,
+                path,
+                available.join(", ")
+            ),
+            ChartError::Conversion {
+                path,
+                row,
+                column,
+                value,
+                expected,
+            } => write!(
+                f,
+                "{}: expected {}, found '{}'",
+                location(path, Some(*row), Some(column)),
+                expected,
+                value
+            ),
+            ChartError::EmptyData { path, reason } => match reason {
+                Some(reason) => write!(f, "'{}' has no data to plot: {}", path, reason),
+                None => write!(f, "'{}' contains no data rows", path),
+            },
+            ChartError::InvalidData {
+                path,
+                row,
+                column,
+                message,
+            } => write!(f, "{}: {}", location(path, *row, column.as_deref()), message),
+            ChartError::Render { path, message } => write!(f, "Could not draw '{}': {}", path, message),
+            ChartError::Output { path, message } => write!(f, "Cannot write '{}': {}", path, message),
+            ChartError::NoInput => write!(f, "The chart needs at least one input file"),
+            ChartError::NoOutput => write!(f, "The chart needs an output file"),
+        }
+    }
+}
+
+impl Error for ChartError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ChartError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_the_file_row_and_column() {
+        let error = ChartError::conversion("data.csv", 3, "Value", "abc", "a number");
+        assert_eq!(error.to_string(), "'data.csv' row 3, column 'Value': expected a number, found 'abc'");
+        let error = ChartError::invalid_column("data.csv", "Group", "takes one value column");
+        assert_eq!(error.to_string(), "'data.csv' column 'Group': takes one value column");
+        assert_eq!(ChartError::invalid("data.csv", "is odd").to_string(), "'data.csv': is odd");
+        assert_eq!(ChartError::NoInput.to_string(), "The chart needs at least one input file");
+    }
+
+    #[test]
+    fn wraps_only_drawing_errors_as_render_errors() {
+        let data_error = ChartError::render("out.png", ChartError::invalid("in.csv", "is odd").into());
+        assert!(matches!(data_error.downcast_ref(), Some(ChartError::InvalidData { .. })));
+        let drawing_error = ChartError::render("out.png", "disk full".into());
+        assert_eq!(drawing_error.to_string(), "Could not draw 'out.png': disk full");
+    }
+}
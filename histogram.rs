@@ -0,0 +1,196 @@
+// src/histogram.rs
+
+use crate::templating::Binning;
+
+// Equal-width bins: bin i covers [start + i * width, start + (i + 1) * width)
+#[derive(Clone, Copy, Debug)]
+pub struct Bins {
+    pub start: f64,
+    pub width: f64,
+    pub count: usize,
+}
+
+impl Bins {
+    pub fn end(&self) -> f64 {
+        self.start + self.width * self.count as f64
+    }
+
+    // Number of values in each bin; the largest value falls in the last bin
+    pub fn counts(&self, values: &[f64]) -> Vec<usize> {
+        let mut counts = vec![0; self.count];
+        for value in values {
+            let index = ((value - self.start) / self.width).floor();
+            if index >= 0.0 {
+                counts[(index as usize).min(self.count - 1)] += 1;
+            }
+        }
+        counts
+    }
+}
+
+// Linear interpolation between the nearest ranks of sorted values; NaN when there are none
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    let position = q * (sorted.len() - 1) as f64;
+    let (low, high) = (position.floor() as usize, position.ceil() as usize);
+    sorted[low] + (sorted[high] - sorted[low]) * (position - low as f64)
+}
+
+fn sorted(values: &[f64]) -> Vec<f64> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    sorted
+}
+
+// Bins shared by every distribution, so that they line up when drawn over each other.
+// Rules are applied to all values together; `values` must not be empty.
+pub fn bins(values: &[f64], binning: Binning) -> Bins {
+    let sorted = sorted(values);
+    let (min, max) = (sorted[0], sorted[sorted.len() - 1]);
+    let span = max - min;
+    let sturges = || (values.len() as f64).log2().ceil() as usize + 1;
+
+    let width = match binning {
+        Binning::Width(width) => width,
+        _ if span == 0.0 => 1.0,
+        Binning::Count(count) => span / count as f64,
+        Binning::Sturges => span / sturges() as f64,
+        Binning::FreedmanDiaconis => {
+            let iqr = quantile(&sorted, 0.75) - quantile(&sorted, 0.25);
+            if iqr > 0.0 {
+                2.0 * iqr / (values.len() as f64).cbrt()
+            } else {
+                // Most values are equal, so the rule has nothing to go on
+                span / sturges() as f64
+            }
+        }
+    };
+
+    // Fixed widths start on a multiple of the width, e.g. 0-5, 5-10, ...
+    let start = match binning {
+        Binning::Width(width) => (min / width).floor() * width,
+        _ if span == 0.0 => min - 0.5,
+        _ => min,
+    };
+    // Casting saturates, so a width far too small for the data gives usize::MAX bins
+    let count = (((max - start) / width).ceil() as usize).max(1);
+    // The maximum sits on the last edge of a rule's bins but starts a new bin of a fixed width
+    let count = if matches!(binning, Binning::Width(_)) && start + width * count as f64 <= max {
+        count.saturating_add(1)
+    } else {
+        count
+    };
+    Bins { start, width, count }
+}
+
+// Gaussian kernel density estimate with Silverman's bandwidth, sampled at `samples` points
+// across [from, to]; the density is scaled by `scale`, e.g. to match bin counts.
+// There is no curve for fewer than two distinct values.
+pub fn density_curve(values: &[f64], from: f64, to: f64, samples: usize, scale: f64) -> Vec<(f64, f64)> {
+    if values.is_empty() {
+        return Vec::new();
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let deviation = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0).max(1.0)).sqrt();
+    let sorted = sorted(values);
+    let iqr = quantile(&sorted, 0.75) - quantile(&sorted, 0.25);
+    let spread = if iqr > 0.0 { deviation.min(iqr / 1.34) } else { deviation };
+    let bandwidth = 0.9 * spread * n.powf(-0.2);
+    if bandwidth.is_nan() || bandwidth <= 0.0 {
+        return Vec::new();
+    }
+
+    let norm = scale / (n * bandwidth * (2.0 * std::f64::consts::PI).sqrt());
+    (0..samples)
+        .map(|index| {
+            let x = from + (to - from) * index as f64 / (samples - 1).max(1) as f64;
+            let sum: f64 = values.iter().map(|v| (-0.5 * ((x - v) / bandwidth).powi(2)).exp()).sum();
+            (x, sum * norm)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(from: i32, to: i32) -> Vec<f64> {
+        (from..=to).map(f64::from).collect()
+    }
+
+    #[test]
+    fn splits_the_range_into_a_bin_count() {
+        let bins = bins(&range(0, 10), Binning::Count(5));
+        assert_eq!((bins.start, bins.width, bins.count, bins.end()), (0.0, 2.0, 5, 10.0));
+        // The maximum is counted in the last bin rather than a bin of its own
+        assert_eq!(bins.counts(&range(0, 10)), [2, 2, 2, 2, 3]);
+    }
+
+    #[test]
+    fn aligns_fixed_widths_to_multiples_of_the_width() {
+        let bins = bins(&[1.0, 9.9], Binning::Width(2.5));
+        assert_eq!((bins.start, bins.count, bins.end()), (0.0, 4, 10.0));
+
+        // A value on a bin edge starts the next bin, the maximum included
+        let bins = super::bins(&[0.0, 5.0, 10.0], Binning::Width(5.0));
+        assert_eq!((bins.start, bins.count), (0.0, 3));
+        assert_eq!(bins.counts(&[0.0, 4.9, 5.0, 10.0]), [2, 1, 1]);
+
+        let bins = super::bins(&[-3.0, 4.0], Binning::Width(2.0));
+        assert_eq!((bins.start, bins.count), (-4.0, 5));
+    }
+
+    #[test]
+    fn follows_the_sturges_and_freedman_diaconis_rules() {
+        // ceil(log2(100)) + 1 = 8 bins
+        let bins = bins(&range(1, 100), Binning::Sturges);
+        assert_eq!(bins.count, 8);
+        assert!((bins.width - 99.0 / 8.0).abs() < 1e-9);
+
+        // IQR of 1..=8 is 3.5, so the width is 2 * 3.5 / cbrt(8)
+        let bins = super::bins(&range(1, 8), Binning::FreedmanDiaconis);
+        assert_eq!((bins.start, bins.width, bins.count), (1.0, 3.5, 2));
+
+        // No spread between the quartiles falls back to Sturges: ceil(log2(8)) + 1 = 4 bins
+        let bins = super::bins(&[1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 5.0], Binning::FreedmanDiaconis);
+        assert_eq!((bins.width, bins.count), (1.0, 4));
+    }
+
+    #[test]
+    fn centers_a_single_value_in_one_bin() {
+        for binning in [Binning::Count(10), Binning::Sturges, Binning::FreedmanDiaconis] {
+            let bins = bins(&[3.0, 3.0, 3.0], binning);
+            assert_eq!((bins.start, bins.width, bins.count), (2.5, 1.0, 1));
+            assert_eq!(bins.counts(&[3.0, 3.0, 3.0]), [3]);
+        }
+        let bins = bins(&[3.0], Binning::Width(2.0));
+        assert_eq!((bins.start, bins.count), (2.0, 1));
+    }
+
+    #[test]
+    fn saturates_the_count_of_far_too_narrow_bins() {
+        assert_eq!(bins(&[0.0, 1.0], Binning::Width(1e-300)).count, usize::MAX);
+    }
+
+    #[test]
+    fn integrates_the_density_to_about_one() {
+        let values: Vec<f64> = (0..200).map(|i| ((i * 37) % 101) as f64 / 10.0 + (i % 7) as f64).collect();
+        let curve = density_curve(&values, -10.0, 30.0, 2001, 1.0);
+        let area: f64 = curve.windows(2).map(|pair| (pair[1].0 - pair[0].0) * (pair[0].1 + pair[1].1) / 2.0).sum();
+        assert!((area - 1.0).abs() < 1e-3, "{}", area);
+
+        // Scaled by n * width, the curve is in the same units as the bin counts
+        let scaled = density_curve(&values, -10.0, 30.0, 2001, 200.0 * 2.0);
+        assert!((scaled[1000].1 - curve[1000].1 * 400.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn draws_no_density_curve_without_spread() {
+        assert!(density_curve(&[], 0.0, 1.0, 10, 1.0).is_empty());
+        assert!(density_curve(&[2.0, 2.0], 0.0, 4.0, 10, 1.0).is_empty());
+        assert!(quantile(&[], 0.5).is_nan());
+    }
+}
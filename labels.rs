@@ -0,0 +1,95 @@
+// src/labels.rs
+
+// Where a label goes relative to the point it belongs to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Side {
+    Above,
+    Below,
+    Left,
+    Right,
+    Center,
+}
+
+// Keeps labels from overlapping each other or leaving the plotting area: each label takes the
+// first of its preferred sides that is free, and is left out when none is
+pub struct LabelLayout {
+    bounds: [(i32, i32); 2],
+    gap: i32,
+    taken: Vec<[(i32, i32); 2]>,
+}
+
+impl LabelLayout {
+    // `bounds` are the top-left and bottom-right pixels labels must stay within,
+    // `gap` the distance in pixels between a label and its point
+    pub fn new(bounds: [(i32, i32); 2], gap: i32) -> Self {
+        LabelLayout {
+            bounds,
+            gap,
+            taken: Vec::new(),
+        }
+    }
+
+    // Reserves space for a label of `size` pixels beside `anchor` and returns its top-left corner
+    pub fn place(&mut self, anchor: (i32, i32), size: (i32, i32), sides: &[Side]) -> Option<(i32, i32)> {
+        let (width, height) = size;
+        let (x, y) = anchor;
+        for side in sides {
+            let corner = match side {
+                Side::Above => (x - width / 2, y - self.gap - height),
+                Side::Below => (x - width / 2, y + self.gap),
+                Side::Left => (x - self.gap - width, y - height / 2),
+                Side::Right => (x + self.gap, y - height / 2),
+                Side::Center => (x - width / 2, y - height / 2),
+            };
+            let rect = [corner, (corner.0 + width, corner.1 + height)];
+            let inside = rect[0].0 >= self.bounds[0].0
+                && rect[0].1 >= self.bounds[0].1
+                && rect[1].0 <= self.bounds[1].0
+                && rect[1].1 <= self.bounds[1].1;
+            if inside && !self.taken.iter().any(|other| overlaps(&rect, other)) {
+                self.taken.push(rect);
+                return Some(corner);
+            }
+        }
+        None
+    }
+}
+
+fn overlaps(a: &[(i32, i32); 2], b: &[(i32, i32); 2]) -> bool {
+    a[0].0 < b[1].0 && b[0].0 < a[1].0 && a[0].1 < b[1].1 && b[0].1 < a[1].1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn puts_labels_on_the_requested_side_of_their_point() {
+        let mut layout = LabelLayout::new([(0, 0), (200, 200)], 4);
+        assert_eq!(layout.place((100, 40), (20, 10), &[Side::Above]), Some((90, 26)));
+        assert_eq!(layout.place((100, 100), (20, 10), &[Side::Below]), Some((90, 104)));
+        assert_eq!(layout.place((40, 150), (20, 10), &[Side::Left]), Some((16, 145)));
+        assert_eq!(layout.place((160, 150), (20, 10), &[Side::Right]), Some((164, 145)));
+        assert_eq!(layout.place((100, 180), (20, 10), &[Side::Center]), Some((90, 175)));
+    }
+
+    #[test]
+    fn keeps_labels_inside_the_bounds() {
+        let mut layout = LabelLayout::new([(10, 10), (110, 110)], 2);
+        // Above would cross the top edge, so the label goes below
+        assert_eq!(layout.place((60, 15), (20, 10), &[Side::Above, Side::Below]), Some((50, 17)));
+        // Touching the edge is still inside
+        assert_eq!(layout.place((100, 60), (8, 10), &[Side::Right]), Some((102, 55)));
+        assert_eq!(layout.place((108, 80), (8, 10), &[Side::Right]), None);
+    }
+
+    #[test]
+    fn moves_to_the_next_free_side_or_drops_the_label() {
+        let mut layout = LabelLayout::new([(0, 0), (200, 200)], 0);
+        assert_eq!(layout.place((100, 100), (20, 10), &[Side::Above]), Some((90, 90)));
+        assert_eq!(layout.place((105, 100), (20, 10), &[Side::Above, Side::Below]), Some((95, 100)));
+        assert_eq!(layout.place((100, 105), (20, 10), &[Side::Above, Side::Center]), None);
+        // Sharing an edge is not an overlap
+        assert_eq!(layout.place((80, 100), (20, 10), &[Side::Left, Side::Above]), Some((60, 95)));
+    }
+}
@@ -1,58 +1,631 @@
+mod axis;
+mod charts;
+mod cli;
 mod data_loading;
+mod datetime;
+mod error;
+mod histogram;
+mod labels;
+mod numbers;
+mod palette;
 mod templating;
-use csv::ReaderBuilder;
+use std::error::Error;
+use std::path::Path;
 use plotters::prelude::*;
-use rand::Rng;
-use csv::Reader;
+use plotters::coord::Shift;
+use plotters::style::text_anchor::{HPos, Pos, VPos};
+use charts::Chart;
+use error::ChartError;
+use labels::{LabelLayout, Side};
+use templating::{BarMode, Binning, LineMode, OutputFormat, PieAnnotation, RadarGrid, RadarScale, SortOrder, Template};
+
+// Creates the drawing area for the template's output file and runs `$draw` on it.
+// The body is expanded once per backend, so PNG and SVG share the same drawing code.
+macro_rules! with_backend {
+    ($template:expr, $default_size:expr, |$root:ident| $draw:expr) => {{
+        let template: &Template = $template;
+        let size = template.pixel_size($default_size);
+        match template.output_format()? {
+            OutputFormat::Png => {
+                let $root = BitMapBackend::new(&template.output, size).into_drawing_area();
+                $draw.map_err(|e| ChartError::render(&template.output, e))?;
+                $root.present().map_err(|e| ChartError::render(&template.output, Box::new(e)))?;
+            }
+            OutputFormat::Svg => {
+                let $root = SVGBackend::new(&template.output, size).into_drawing_area();
+                $draw.map_err(|e| ChartError::render(&template.output, e))?;
+                $root.present().map_err(|e| ChartError::render(&template.output, Box::new(e)))?;
+            }
+        }
+        Ok(())
+    }};
+}
 
-fn plot_bar_chart(
+// Columns a bar chart reads, as written in the CSV header
+struct BarColumns {
+    category: String,
+    // Long format: each row is one bar of the series named in this column
+    group: Option<String>,
+    values: Vec<String>,
+}
+
+// Category and value columns from the template. The category defaults to the first column, and
+// the value to the first column, before or after the category, that is not the category, the
+// group column or a color column
+fn resolve_bar_columns(
     data: &[data_loading::DataRow],
-    output_file: &str,
-    chart_title: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let root = BitMapBackend::new(output_file, (640, 480)).into_drawing_area();
-    root.fill(&WHITE)?;
-    let colors = [RED, GREEN, BLUE, YELLOW, MAGENTA, CYAN, BLACK];
+    template: &Template,
+) -> Result<BarColumns, Box<dyn std::error::Error>> {
+    let input = &template.inputs[0];
+    let headers = &data
+        .first()
+        .ok_or_else(|| ChartError::EmptyData {
+            path: input.clone(),
+            reason: None,
+        })?
+        .headers;
+
+    let category = match template.columns.first() {
+        Some(name) if !name.is_empty() => data_loading::find_column(headers, name, input)?,
+        _ => headers.first().map(String::as_str).ok_or_else(|| ChartError::invalid(input, "The file has no columns"))?,
+    };
+    let group = match &template.group_column {
+        Some(name) => Some(data_loading::find_column(headers, name, input)?),
+        None => None,
+    };
+    let values = if template.columns.len() > 1 {
+        template.columns[1..]
+            .iter()
+            .map(|name| data_loading::find_column(headers, name, input).map(str::to_string))
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        let value = headers
+            .iter()
+            .find(|header| {
+                header.as_str() != category && Some(header.as_str()) != group && !header.eq_ignore_ascii_case("color")
+            })
+            .ok_or_else(|| {
+                ChartError::invalid(
+                    input,
+                    format!("Bar charts need a category and a value column, but the file only has: {}", headers.join(", ")),
+                )
+            })?;
+        vec![value.clone()]
+    };
+    if let (Some(group), true) = (group, values.len() > 1) {
+        let message = format!("a bar chart with a group column takes a single value column, found {}", values.len());
+        return Err(ChartError::invalid_column(input, group, message).into());
+    }
 
-    let max_age = data
-        .iter()
-        .filter_map(|row| row.values.get("age").and_then(|s| s.parse::<i32>().ok()))
-        .max()
-        .unwrap_or(0);
-
-    let mut chart = ChartBuilder::on(&root)
-        .caption(chart_title, ("Arial", 40).into_font())
-        .margin(5)
-        .x_label_area_size(50)
-        .y_label_area_size(40)
-        .build_cartesian_2d(0..data.len(), 0..max_age)?;
-
-    chart
-        .configure_mesh()
-        .x_labels(data.len())
-        .x_label_formatter(&|x| {
-            data.get(*x as usize)
-                .and_then(|row| row.values.get("name"))
-                .map(ToString::to_string)
-                .unwrap_or_else(|| "".to_string())
+    Ok(BarColumns {
+        category: category.to_string(),
+        group: group.map(str::to_string),
+        values,
+    })
+}
+
+struct BarSeries {
+    name: String,
+    // One value per category; NaN where the series has no bar
+    values: Vec<f64>,
+    // One color per category
+    colors: Vec<RGBAColor>,
+}
+
+// Categories in file order, with one series per value column or per group
+struct BarChartData {
+    categories: Vec<String>,
+    series: Vec<BarSeries>,
+}
+
+const BAR_COLORS: &[RGBColor] = &[RED, GREEN, BLUE, YELLOW, MAGENTA, CYAN, BLACK];
+// Share of each category's slot covered by its bars
+const BAR_WIDTH: f64 = 0.8;
+
+#[derive(Default)]
+struct BarChart;
+
+impl Chart for BarChart {
+    type Data = BarChartData;
+
+    fn load(&self, template: &Template) -> Result<Self::Data, Box<dyn Error>> {
+        let rows = data_loading::load_csv(&template.inputs[0])?;
+        let columns = resolve_bar_columns(&rows, template)?;
+        let (mut data, skipped) = match &columns.group {
+            Some(group) => read_grouped_bars(&rows, template, &columns.category, group, &columns.values[0])?,
+            None => read_bar_columns(&rows, template, &columns)?,
+        };
+
+        // A single series keeps a color per bar; several series get one color each
+        if data.series.len() > 1 || columns.group.is_some() {
+            let names: Vec<&str> = data.series.iter().map(|s| s.name.as_str()).collect();
+            let colors = template.colors_for(&names, BAR_COLORS);
+            for (series, color) in data.series.iter_mut().zip(colors) {
+                series.colors = vec![color.to_rgba(); data.categories.len()];
+            }
+        }
+        arrange_bar_categories(&mut data, template);
+
+        // Rows without numeric values are left out and reported, rather than failing the whole chart
+        if !skipped.is_empty() {
+            eprintln!(
+                "Warning: skipped {} row(s) of '{}' whose {} is not a number: rows {}",
+                skipped.len(),
+                template.inputs[0],
+                columns.values.iter().map(|name| format!("'{}'", name)).collect::<Vec<_>>().join(" or "),
+                skipped.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+            );
+        }
+        Ok(data)
+    }
+
+    fn validate(&self, data: &Self::Data, template: &Template) -> Result<(), Box<dyn Error>> {
+        if data.categories.is_empty() {
+            return Err(ChartError::EmptyData {
+                path: template.inputs[0].clone(),
+                reason: Some("no row has a numeric value".to_string()),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    fn render(&self, data: &Self::Data, template: &Template) -> Result<(), Box<dyn Error>> {
+        plot_bar_chart(data, template)
+    }
+}
+
+// One series per value column; returns the 1-based rows skipped for a non-numeric value
+fn read_bar_columns(
+    rows: &[data_loading::DataRow],
+    template: &Template,
+    columns: &BarColumns,
+) -> Result<(BarChartData, Vec<usize>), Box<dyn Error>> {
+    let row_colors = bar_colors(rows, template, &columns.category)?;
+    let mut data = BarChartData {
+        categories: Vec::new(),
+        series: columns
+            .values
+            .iter()
+            .map(|name| BarSeries {
+                name: name.clone(),
+                values: Vec::new(),
+                colors: Vec::new(),
+            })
+            .collect(),
+    };
+    let mut skipped = Vec::new();
+    for (index, (row, color)) in rows.iter().zip(row_colors).enumerate() {
+        let values: Option<Vec<f64>> = columns
+            .values
+            .iter()
+            .map(|name| {
+                let text = row.values.get(name).map_or("", String::as_str);
+                text.trim().parse::<f64>().ok().filter(|value| value.is_finite())
+            })
+            .collect();
+        let Some(values) = values else {
+            skipped.push(index + 1);
+            continue;
+        };
+        data.categories.push(row.values.get(&columns.category).cloned().unwrap_or_default());
+        for (series, value) in data.series.iter_mut().zip(values) {
+            series.values.push(value);
+            series.colors.push(color);
+        }
+    }
+    Ok((data, skipped))
+}
+
+// One series per distinct value of the group column, each row giving one bar;
+// categories and series appear in the order they are first seen
+fn read_grouped_bars(
+    rows: &[data_loading::DataRow],
+    template: &Template,
+    category_column: &str,
+    group_column: &str,
+    value_column: &str,
+) -> Result<(BarChartData, Vec<usize>), Box<dyn Error>> {
+    let input = &template.inputs[0];
+    let mut data = BarChartData {
+        categories: Vec::new(),
+        series: Vec::new(),
+    };
+    let mut skipped = Vec::new();
+    for (index, row) in rows.iter().enumerate() {
+        let cell = |column: &str| row.values.get(column).cloned().unwrap_or_default();
+        let value = match cell(value_column).trim().parse::<f64>() {
+            Ok(value) if value.is_finite() => value,
+            _ => {
+                skipped.push(index + 1);
+                continue;
+            }
+        };
+
+        let (category, name) = (cell(category_column), cell(group_column));
+        let category_index = match data.categories.iter().position(|c| *c == category) {
+            Some(position) => position,
+            None => {
+                data.categories.push(category.clone());
+                for series in &mut data.series {
+                    series.values.push(f64::NAN);
+                }
+                data.categories.len() - 1
+            }
+        };
+        let series_index = match data.series.iter().position(|s| s.name == name) {
+            Some(position) => position,
+            None => {
+                data.series.push(BarSeries {
+                    name,
+                    values: vec![f64::NAN; data.categories.len()],
+                    colors: Vec::new(),
+                });
+                data.series.len() - 1
+            }
+        };
+
+        let series = &mut data.series[series_index];
+        if !series.values[category_index].is_nan() {
+            return Err(ChartError::InvalidData {
+                path: input.clone(),
+                row: Some(index + 1),
+                column: Some(group_column.to_string()),
+                message: format!("series '{}' already has a value for '{}'", series.name, category),
+            }
+            .into());
+        }
+        series.values[category_index] = value;
+    }
+    Ok((data, skipped))
+}
+
+// Sorts the categories by their total over all series and, with a top-N limit, adds up the
+// smaller ones into a final "Other" category. A limit alone keeps the largest first.
+fn arrange_bar_categories(data: &mut BarChartData, template: &Template) {
+    let order = match (template.sort, template.top) {
+        (Some(order), _) => order,
+        (None, Some(_)) => SortOrder::Descending,
+        (None, None) => return,
+    };
+    let totals: Vec<f64> = (0..data.categories.len())
+        .map(|category| {
+            data.series
+                .iter()
+                .map(|series| series.values[category])
+                .filter(|value| value.is_finite())
+                .sum()
         })
+        .collect();
+
+    // The largest come first, so the top N are the ones kept; ties stay in file order
+    let mut ranking: Vec<usize> = (0..data.categories.len()).collect();
+    ranking.sort_by(|a, b| totals[*b].total_cmp(&totals[*a]));
+    let split = template.top.unwrap_or(ranking.len()).min(ranking.len());
+    let (mut kept, rest) = (ranking[..split].to_vec(), ranking[split..].to_vec());
+    if order == SortOrder::Ascending {
+        kept.sort_by(|a, b| totals[*a].total_cmp(&totals[*b]));
+    }
+
+    data.categories = kept.iter().map(|index| data.categories[*index].clone()).collect();
+    if !rest.is_empty() {
+        data.categories.push("Other".to_string());
+    }
+    for series in &mut data.series {
+        let mut values: Vec<f64> = kept.iter().map(|index| series.values[*index]).collect();
+        let mut colors: Vec<RGBAColor> = kept.iter().map(|index| series.colors[*index]).collect();
+        if !rest.is_empty() {
+            let others: Vec<f64> = rest.iter().map(|index| series.values[*index]).filter(|v| v.is_finite()).collect();
+            values.push(if others.is_empty() { f64::NAN } else { others.iter().sum() });
+            // A series drawn in one color keeps it; bars colored by category get a neutral gray
+            let first = series.colors[0];
+            let same_color = series.colors.iter().all(|color| color.rgb() == first.rgb());
+            colors.push(if same_color { first } else { RGBColor(160, 160, 160).to_rgba() });
+        }
+        series.values = values;
+        series.colors = colors;
+    }
+}
+
+// Bottom and top of every bar, per series and category. Stacked bars pile up from zero,
+// positive values upwards and negative values downwards; percent mode first scales each
+// category so the sizes of its bars add up to 100.
+fn bar_segments(data: &BarChartData, mode: BarMode) -> Vec<Vec<(f64, f64)>> {
+    let category_count = data.categories.len();
+    let totals: Vec<f64> = (0..category_count)
+        .map(|category| {
+            data.series
+                .iter()
+                .map(|series| series.values[category])
+                .filter(|value| value.is_finite())
+                .map(f64::abs)
+                .sum()
+        })
+        .collect();
+
+    let mut positive = vec![0.0; category_count];
+    let mut negative = vec![0.0; category_count];
+    let mut segments = Vec::new();
+    for series in &data.series {
+        let mut bars = Vec::new();
+        for (category, &value) in series.values.iter().enumerate() {
+            if !value.is_finite() {
+                bars.push((f64::NAN, f64::NAN));
+                continue;
+            }
+            let value = match mode {
+                BarMode::Grouped => {
+                    bars.push((0.0, value));
+                    continue;
+                }
+                BarMode::Stacked => value,
+                BarMode::Percent if totals[category] > 0.0 => value / totals[category] * 100.0,
+                BarMode::Percent => 0.0,
+            };
+            let base = if value < 0.0 { &mut negative[category] } else { &mut positive[category] };
+            bars.push((*base, *base + value));
+            *base += value;
+        }
+        segments.push(bars);
+    }
+    segments
+}
+
+// The value axis always includes the zero baseline, with padding on the sides that have bars.
+// Its limits come from the y axis settings, or the x axis for horizontal bars.
+fn bar_value_range(
+    segments: &[Vec<(f64, f64)>],
+    template: &Template,
+) -> Result<std::ops::Range<f64>, Box<dyn Error>> {
+    let value_axis = if template.horizontal { &template.x_axis } else { &template.y_axis };
+    let ends = segments.iter().flatten().flat_map(|&(bottom, top)| [bottom, top]);
+    let (min, max) = axis::data_extent(ends).unwrap_or((0.0, 0.0));
+    if template.bar_mode == BarMode::Percent {
+        return axis::resolve(value_axis, (if min < 0.0 { -100.0 } else { 0.0 }, 100.0));
+    }
+
+    let (padded_min, padded_max) = axis::padded((min.min(0.0), max.max(0.0)), 0.05);
+    let low = if min < 0.0 { padded_min } else { 0.0 };
+    // All-zero values still get a range above the baseline
+    let high = if max > 0.0 || min >= 0.0 { padded_max } else { 0.0 };
+    axis::resolve(value_axis, (low, high))
+}
+
+fn plot_bar_chart(data: &BarChartData, template: &Template) -> Result<(), Box<dyn std::error::Error>> {
+    let segments = bar_segments(data, template.bar_mode);
+    let value_range = bar_value_range(&segments, template)?;
+
+    with_backend!(template, BAR_CHART_SIZE, |root| draw_bar_chart(
+        &root,
+        data,
+        &segments,
+        template,
+        value_range.clone()
+    ))
+}
+
+// One color per row: from a `color` column if the file has one, otherwise by category
+fn bar_colors(
+    data: &[data_loading::DataRow],
+    template: &Template,
+    category_column: &str,
+) -> Result<Vec<RGBAColor>, Box<dyn std::error::Error>> {
+    let input = &template.inputs[0];
+    let color_column = data
+        .first()
+        .and_then(|row| row.headers.iter().find(|header| header.eq_ignore_ascii_case("color")));
+
+    match color_column {
+        Some(column) if column != category_column => data
+            .iter()
+            .enumerate()
+            .map(|(row, values)| {
+                let text = values.values.get(column).map_or("", String::as_str);
+                parse_color_cell(input, row, column, text)
+            })
+            .collect(),
+        _ => {
+            let categories: Vec<&str> = data
+                .iter()
+                .map(|row| row.values.get(category_column).map_or("", String::as_str))
+                .collect();
+            let colors = template.colors_for(&categories, BAR_COLORS);
+            Ok(colors.iter().map(|color| color.to_rgba()).collect())
+        }
+    }
+}
+
+fn draw_bar_chart<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    data: &BarChartData,
+    segments: &[Vec<(f64, f64)>],
+    template: &Template,
+    value_range: std::ops::Range<f64>,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    DB::ErrorType: 'static,
+{
+    root.fill(&WHITE)?;
+
+    // Category i is centered on i, counted from the left, or from the top for horizontal bars
+    let horizontal = template.horizontal;
+    let category_count = data.categories.len();
+    let category_range = -0.5..category_count as f64 - 0.5;
+    let position = |category: usize| {
+        if horizontal { (category_count - 1 - category) as f64 } else { category as f64 }
+    };
+    // Turns (category axis, value axis) coordinates into chart coordinates
+    let point = |along: f64, value: f64| if horizontal { (value, along) } else { (along, value) };
+
+    // Horizontal bars make room for the longest category name beside the y axis
+    let label_size = template.scaled(12.0);
+    let (x_label_area, y_label_area) = if horizontal {
+        let style = TextStyle::from(("sans-serif", label_size).into_font());
+        let mut widest = 0;
+        for category in &data.categories {
+            widest = widest.max(root.estimate_text_size(category, &style)?.0 as i32);
+        }
+        (template.px(40), widest + template.px(10))
+    } else {
+        (template.px(50), template.px(40))
+    };
+    let (x_range, y_range) = if horizontal {
+        (value_range.clone(), category_range.clone())
+    } else {
+        (category_range.clone(), value_range.clone())
+    };
+    let mut chart = ChartBuilder::on(root)
+        .caption(&template.title, ("Arial", template.scaled(40.0)).into_font())
+        .margin(template.px(5))
+        .x_label_area_size(x_label_area)
+        .y_label_area_size(y_label_area)
+        .build_cartesian_2d(x_range, y_range)?;
+
+    let name_formatter = |along: &f64| {
+        let index = along.round();
+        if (along - index).abs() > 1e-6 || index < 0.0 || index as usize >= category_count {
+            return String::new();
+        }
+        let category = if horizontal { category_count - 1 - index as usize } else { index as usize };
+        data.categories[category].clone()
+    };
+    let percent_formatter = |value: &f64| format!("{}%", value);
+    let percent = template.bar_mode == BarMode::Percent;
+    let mut mesh = chart.configure_mesh();
+    if horizontal {
+        mesh.disable_y_mesh().y_labels(category_count).y_label_formatter(&name_formatter);
+        if percent {
+            mesh.x_label_formatter(&percent_formatter);
+        }
+    } else {
+        mesh.disable_x_mesh().x_labels(category_count).x_label_formatter(&name_formatter);
+        if percent {
+            mesh.y_label_formatter(&percent_formatter);
+        }
+    }
+    if let Some(label) = &template.x_axis.label {
+        mesh.x_desc(label.as_str());
+    }
+    if let Some(label) = &template.y_axis.label {
+        mesh.y_desc(label.as_str());
+    }
+    mesh.label_style(("sans-serif", label_size))
+        .axis_desc_style(("sans-serif", template.scaled(15.0)))
         .draw()?;
 
-    for (index, row) in data.iter().enumerate() {
-        if let Some(age_str) = row.values.get("age") {
-            if let Ok(age) = age_str.parse::<i32>() {
-                let color = colors[index % colors.len()];
-                chart.draw_series(std::iter::once(Rectangle::new(
-                    [(index, 0), (index + 1, age)],
-                    color.filled(),
-                )))?;
+    // Grouped series share each category's slot side by side, the first series on the left
+    // or at the top; stacked series fill all of it
+    let series_count = data.series.len();
+    let swatch = template.px(5);
+    // Value labels go past the end of grouped bars and in the middle of stacked ones
+    let mut value_labels = Vec::new();
+    for (index, (series, bars)) in data.series.iter().zip(segments).enumerate() {
+        let (offset, width) = match template.bar_mode {
+            BarMode::Grouped => {
+                let width = BAR_WIDTH / series_count as f64;
+                let slot = if horizontal { series_count - 1 - index } else { index };
+                (-BAR_WIDTH / 2.0 + slot as f64 * width, width)
             }
+            BarMode::Stacked | BarMode::Percent => (-BAR_WIDTH / 2.0, BAR_WIDTH),
+        };
+        let rectangles = bars
+            .iter()
+            .zip(&series.colors)
+            .enumerate()
+            .filter(|(_, (segment, _))| segment.0.is_finite() && segment.1.is_finite())
+            .map(|(category, (&(bottom, top), color))| {
+                let low = position(category) + offset;
+                Rectangle::new([point(low, bottom), point(low + width, top)], color.filled())
+            });
+        for (category, &(bottom, top)) in bars.iter().enumerate().filter(|(_, segment)| segment.1.is_finite()) {
+            let center = position(category) + offset + width / 2.0;
+            let label = match template.bar_mode {
+                BarMode::Grouped => {
+                    let sides = match (horizontal, top < 0.0) {
+                        (false, false) => [Side::Above, Side::Center],
+                        (false, true) => [Side::Below, Side::Center],
+                        (true, false) => [Side::Right, Side::Center],
+                        (true, true) => [Side::Left, Side::Center],
+                    };
+                    (point(center, top), template.number_format.format(top), sides.to_vec())
+                }
+                BarMode::Stacked => {
+                    let text = template.number_format.format(top - bottom);
+                    (point(center, (bottom + top) / 2.0), text, vec![Side::Center])
+                }
+                BarMode::Percent => {
+                    let format = numbers::NumberFormat {
+                        style: numbers::NumberStyle::Percent,
+                        ..template.number_format
+                    };
+                    let text = format.format((top - bottom) / 100.0);
+                    (point(center, (bottom + top) / 2.0), text, vec![Side::Center])
+                }
+            };
+            value_labels.push(label);
         }
+        let annotation = chart.draw_series(rectangles)?;
+        if series_count > 1 {
+            let color = series.colors.first().copied().unwrap_or(BLACK.to_rgba());
+            annotation
+                .label(series.name.as_str())
+                .legend(move |(x, y)| Rectangle::new([(x, y - swatch), (x + 2 * swatch, y + swatch)], color.filled()));
+        }
+    }
+    if value_range.start < 0.0 && value_range.end > 0.0 {
+        let baseline = [point(category_range.start, 0.0), point(category_range.end, 0.0)];
+        chart.draw_series(LineSeries::new(baseline, &BLACK))?;
+    }
+    if template.value_labels {
+        let mut layout = value_label_layout(chart.plotting_area().get_pixel_range(), template);
+        let style = value_label_style(template);
+        for (coord, text, sides) in &value_labels {
+            draw_value_label(root, &mut layout, text, chart.backend_coord(coord), sides, &style)?;
+        }
+    }
+
+    if series_count > 1 {
+        chart
+            .configure_series_labels()
+            .label_font(("sans-serif", template.scaled(15.0)))
+            .legend_area_size(template.px(30))
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
     }
 
     Ok(())
 }
 
+fn value_label_style(template: &Template) -> TextStyle<'static> {
+    TextStyle::from(("sans-serif", template.scaled(11.0)).into_font()).color(&BLACK)
+}
+
+// Value labels stay inside the plotting area, given as its pixel ranges
+fn value_label_layout(pixels: (std::ops::Range<i32>, std::ops::Range<i32>), template: &Template) -> LabelLayout {
+    let (x, y) = pixels;
+    LabelLayout::new([(x.start, y.start), (x.end, y.end)], template.px(3))
+}
+
+// Draws a value label beside `anchor` (in pixels) on the first free side, or leaves it out
+fn draw_value_label<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    layout: &mut LabelLayout,
+    text: &str,
+    anchor: (i32, i32),
+    sides: &[Side],
+    style: &TextStyle,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let (width, height) = root.estimate_text_size(text, style)?;
+    if let Some(corner) = layout.place(anchor, (width as i32, height as i32), sides) {
+        root.draw(&Text::new(text.to_string(), corner, style.clone()))?;
+    }
+    Ok(())
+}
+
 fn get_user_input(prompt: &str) -> String {
     println!("{}", prompt);
     let mut input = String::new();
@@ -68,609 +641,2439 @@ fn get_user_input(prompt: &str) -> String {
     input
 }
 
+const BAR_CHART_SIZE: (u32, u32) = (640, 480);
+const SCATTER_PLOT_SIZE: (u32, u32) = (600, 400);
+const PIE_CHART_SIZE: (u32, u32) = (350, 350);
+const LINE_AREA_CHART_SIZE: (u32, u32) = (600, 400);
+const RADAR_CHART_SIZE: (u32, u32) = (800, 800);
+const HISTOGRAM_SIZE: (u32, u32) = (640, 480);
+const OUTPUT_EXTENSIONS: &[&str] = &[".png", ".svg"];
+
+// Renders any chart described by a template, whether it came from the menu, the CLI or a spec file
+fn render_template(template: &Template) -> Result<(), Box<dyn Error>> {
+    if template.inputs.is_empty() {
+        return Err(ChartError::NoInput.into());
+    }
+    if template.output.is_empty() {
+        return Err(ChartError::NoOutput.into());
+    }
+    // Checked before reading any data, so a mistyped file name fails fast
+    template.output_format()?;
+
+    (charts::entry(template.visualization_type).render)(template)
+}
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        if cli::is_help(&args) {
+            println!("{}", cli::usage());
+            return;
+        }
+
+        let result = cli::parse_args(&args).and_then(|template| {
+            render_template(&template)?;
+            println!("Chart written to {}", template.output);
+            Ok(())
+        });
+        if let Err(e) = result {
+            eprintln!("Error: {}", e);
+            eprintln!("Run with --help for usage.");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     loop {
         println!("Choose the type of chart you want to generate:");
-        println!("1. Bar Chart (Expected Columns: 'Category', 'Value')");
-        println!("2. Scatter Plot (Expected Columns for two CSVs: 'X1', 'Y1' and 'X2', 'Y2')");
-        println!("3. Pie Chart (Expected Columns: 'Category', 'Percentage')");
-        println!("4. Line and Area Chart (Expected Columns: 'Date', 'Value')");
-        println!("5. Radar Chart (Expected Columns: 'Label', 'Value')"); // Added option for Radar Chart
+        for (number, entry) in charts::CHARTS.iter().enumerate() {
+            println!("{}. {} (Expected Columns: {})", number + 1, entry.description, entry.columns);
+        }
+        let spec_choice = charts::CHARTS.len() + 1;
+        println!("{}. Render from a chart spec file (.toml)", spec_choice);
         println!("'q' or 'quit' to quit program.");
 
-        let mut chart_choice = get_user_input("Enter your choice (1-5, q to quit):");
+        let mut chart_choice = get_user_input(&format!("Enter your choice (1-{}, q to quit):", spec_choice));
 
         if chart_choice == "q" || chart_choice == "quit" {
             println!("Exiting program.");
             break;
         }
 
-        while !["1", "2", "3", "4", "5"].contains(&chart_choice.as_str()) {
-            println!("Invalid choice! Please enter a number between 1 and 5.");
-            chart_choice = get_user_input("Enter your choice (1-5):");
+        let choice = loop {
+            match chart_choice.parse::<usize>() {
+                Ok(number) if (1..=spec_choice).contains(&number) => break number,
+                _ => {
+                    println!("Invalid choice! Please enter a number between 1 and {}.", spec_choice);
+                    chart_choice = get_user_input(&format!("Enter your choice (1-{}):", spec_choice));
+                }
+            }
+        };
+
+        if choice == spec_choice {
+            let spec_file = get_valid_filename("Enter the name of the chart spec file (e.g., 'chart.toml'):", ".toml");
+            match templating::load_template(&spec_file) {
+                Ok(template) => match render_template(&template) {
+                    Ok(()) => println!("Chart written to {}", template.output),
+                    Err(e) => eprintln!("Error rendering chart from spec: {}", e),
+                },
+                Err(e) => eprintln!("Error loading chart spec: {}", e),
+            }
+            continue;
         }
 
-        match chart_choice.as_str() {
-            "1" => {
-                let csv_file_name = get_valid_filename("Enter the name of the CSV file (e.g., 'data.csv'):", ".csv");
-                let output_file_name = get_valid_output_filename("Enter the desired name for the PNG output file (.g., 'output.png'):", ".png");
-                let chart_title = get_user_input("Enter the title for the chart:");
+        let entry = &charts::CHARTS[choice - 1];
+        let mut template = Template::new(entry.kind);
+        template.inputs.push(get_valid_filename("Enter the name of the CSV file (e.g., 'data.csv'):", ".csv"));
+        (entry.prompt)(&mut template);
+        template.output = get_valid_output_filename(
+            "Enter the desired name for the output file (.png or .svg, e.g., 'output.png'):",
+            OUTPUT_EXTENSIONS,
+        );
+        template.title = get_user_input("Enter the title for the chart:");
+        prompt_canvas_size(&mut template, entry.default_size);
+
+        match render_template(&template) {
+            Ok(()) => println!("Chart written to {}", template.output),
+            Err(e) => eprintln!("Error drawing the {} chart: {}", entry.name(), e),
+        }
+    }
+}
 
-                match data_loading::load_csv(&csv_file_name) {
-                    Ok(data) => {
-                        let template_str = "type:table,columns:name|age|gender|city";
-                        let template = templating::parse_template(template_str);
+// Menu questions for each chart kind, asked after the input file
+fn prompt_bar_options(template: &mut Template) {
+    let columns = get_user_input(
+        "Enter the category and value columns (e.g., 'name,age' or 'year,north,south'), or press Enter to use the first two columns:",
+    );
+    template.columns = parse_column_list(&columns);
+    if template.columns.len() <= 2 {
+        let group = get_user_input(
+            "Enter a column that splits the rows into series (e.g., 'region'), or press Enter for a single series:",
+        );
+        if !group.is_empty() {
+            template.group_column = Some(group);
+        }
+    }
+    if template.columns.len() > 2 || template.group_column.is_some() {
+        loop {
+            let mode = get_user_input("Draw the series as 'grouped', 'stacked' or 'percent' bars (press Enter for grouped):");
+            if mode.is_empty() {
+                break;
+            }
+            match template.set_mode(&mode) {
+                Ok(()) => break,
+                Err(e) => println!("{}", e),
+            }
+        }
+    }
+    template.horizontal = get_user_input("Draw the bars horizontally, with the categories down the side? (y/N):")
+        .eq_ignore_ascii_case("y");
+    loop {
+        let order = get_user_input("Order the bars by value, 'asc' or 'desc' (press Enter to keep the file order):");
+        if order.is_empty() {
+            break;
+        }
+        match SortOrder::parse(&order) {
+            Some(order) => {
+                template.sort = Some(order);
+                break;
+            }
+            None => println!("Invalid order '{}'. Please enter asc or desc.", order),
+        }
+    }
+    template.value_labels = get_user_input("Print the values on the chart? (y/N):").eq_ignore_ascii_case("y");
+}
 
-                        if let Err(e) = plot_bar_chart(&data, &output_file_name, &chart_title) {
-                            eprintln!("Error plotting bar chart: {}", e);
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("Error loading CSV file: {}", e);
-                    }
-                }
+fn prompt_scatter_options(template: &mut Template) {
+    loop {
+        let next_file = get_user_input(
+            "Enter the name of another CSV file for the next series, or press Enter to finish:",
+        );
+        if next_file.is_empty() {
+            break;
+        } else if next_file.to_lowercase().ends_with(".csv") {
+            template.inputs.push(next_file);
+        } else {
+            println!("Invalid filename. Please ensure the filename ends with .csv");
+        }
+    }
+    if template.inputs.len() == 1 {
+        let group = get_user_input(
+            "Enter a column that splits the file into series (e.g., 'species'), or press Enter for a single series:",
+        );
+        if !group.is_empty() {
+            template.group_column = Some(group);
+        }
+    }
+    template.value_labels = get_user_input("Print the values on the chart? (y/N):").eq_ignore_ascii_case("y");
+}
+
+fn prompt_pie_options(template: &mut Template) {
+    loop {
+        let annotation = get_user_input(
+            "Annotate slices with 'percent', 'value', 'both' or 'none' (press Enter for percent):",
+        );
+        if annotation.is_empty() {
+            break;
+        }
+        match PieAnnotation::parse(&annotation) {
+            Some(annotation) => {
+                template.pie_annotation = annotation;
+                break;
             }
-            "2" => {
-                let csv_file_name1 = get_valid_filename(
-                    "Enter the name of the first CSV file for scatter plot (e.g., 'data1.csv'):",
-                    ".csv",
-                );
-                let csv_file_name2 = get_valid_filename(
-                    "Enter the name of the second CSV file for scatter plot (e.g., 'data2.csv'):",
-                    ".csv",
-                );
-                let output_file_name = get_valid_output_filename(
-                    "Enter the desired name for the PNG output file (e.g., 'output.png'):",
-                    ".png",
-                );
-                let chart_title = get_user_input("Enter the title for the chart:");
-
-                if let Err(err) = draw_scatter_plot(
-                    &csv_file_name1,
-                    &csv_file_name2,
-                    &output_file_name,
-                    &chart_title,
-                ) {
-                    eprintln!("Error drawing the scatter plot: {}", err);
+            None => println!("Invalid choice '{}'. Please enter percent, value, both or none.", annotation),
+        }
+    }
+    template.legend = get_user_input("Show a legend beside the pie? (y/N):").eq_ignore_ascii_case("y");
+    loop {
+        let donut = get_user_input(
+            "Enter a donut hole size between 0 and 1 (e.g., '0.5'), or press Enter for a solid pie:",
+        );
+        if donut.is_empty() {
+            break;
+        }
+        match donut.parse::<f64>() {
+            Ok(fraction) if fraction > 0.0 && fraction < 1.0 => {
+                template.donut = Some(fraction);
+                break;
+            }
+            _ => println!("Invalid size '{}'. Please enter a number between 0 and 1.", donut),
+        }
+    }
+}
+
+fn prompt_line_options(template: &mut Template) {
+    let date_format = get_user_input(
+        "Enter the date format of the first column (e.g., '%d/%m/%Y'), or press Enter for ISO-8601 dates or numbers:",
+    );
+    if !date_format.is_empty() {
+        template.date_format = Some(date_format);
+    }
+    loop {
+        let mode = get_user_input("Draw value columns as 'line', 'area' or 'stacked' (press Enter for area):");
+        if mode.is_empty() {
+            break;
+        }
+        match template.set_mode(&mode) {
+            Ok(()) => break,
+            Err(e) => println!("{}", e),
+        }
+    }
+    template.value_labels = get_user_input("Print the values on the chart? (y/N):").eq_ignore_ascii_case("y");
+}
+
+fn prompt_histogram_options(template: &mut Template) {
+    let columns = get_user_input(
+        "Enter the column(s) to bin (e.g., 'height' or 'before,after'), or press Enter to use the first column:",
+    );
+    template.columns = parse_column_list(&columns);
+    if template.columns.len() <= 1 {
+        let group = get_user_input(
+            "Enter a column that splits the values into distributions (e.g., 'species'), or press Enter for one:",
+        );
+        if !group.is_empty() {
+            template.group_column = Some(group);
+        }
+    }
+    loop {
+        let bins = get_user_input("Enter a bin count, 'sturges' or 'fd' (press Enter for sturges):");
+        if bins.is_empty() {
+            break;
+        }
+        match Binning::parse(&bins) {
+            Some(binning) => {
+                template.binning = binning;
+                break;
+            }
+            None => println!("Invalid choice '{}'. Please enter a positive number, sturges or fd.", bins),
+        }
+    }
+    template.density = get_user_input("Draw a density curve over the bars? (y/N):").eq_ignore_ascii_case("y");
+}
+
+fn prompt_radar_options(template: &mut Template) {
+    let series = get_user_input(
+        "Enter a column naming the series of each row (e.g., 'player'), or press Enter if each value column is a series:",
+    );
+    if !series.is_empty() {
+        template.group_column = Some(series);
+    }
+}
+
+fn parse_column_list(input: &str) -> Vec<String> {
+    input
+        .split(',')
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+fn get_valid_filename(prompt: &str, extension: &str) -> String {
+    loop {
+        let filename = get_user_input(prompt);
+        if filename.to_lowercase().ends_with(extension) && filename.to_lowercase() != "repeat" {
+            return filename;
+        } else if filename.to_lowercase() == "repeat" {
+            println!("Filename 'Repeat' is not allowed! Please provide a different name.");
+        } else {
+            println!(
+                "Invalid filename. Please ensure the filename ends with {}",
+                extension
+            );
+        }
+    }
+}
+
+// Asks for a canvas size such as "1024x768" and a scale factor; pressing Enter keeps the defaults
+fn prompt_canvas_size(template: &mut Template, default: (u32, u32)) {
+    loop {
+        let size = get_user_input(&format!(
+            "Enter the canvas size in pixels as WIDTHxHEIGHT (e.g., '1024x768'), or press Enter for {}x{}:",
+            default.0, default.1
+        ));
+        if size.is_empty() {
+            break;
+        }
+        let parsed = size
+            .to_lowercase()
+            .split_once('x')
+            .and_then(|(width, height)| Some((width.trim().parse::<u32>().ok()?, height.trim().parse::<u32>().ok()?)));
+        match parsed {
+            Some((width, height)) if width > 0 && height > 0 => {
+                template.width = Some(width);
+                template.height = Some(height);
+                break;
+            }
+            _ => println!("Invalid size '{}'. Please enter two positive numbers, e.g., '1024x768'.", size),
+        }
+    }
+
+    loop {
+        let scale = get_user_input(
+            "Enter a scale factor for high-resolution output (e.g., '2' for slides or print), or press Enter for 1:",
+        );
+        if scale.is_empty() {
+            return;
+        }
+        match scale.parse::<f64>() {
+            Ok(scale) if scale.is_finite() && scale > 0.0 => {
+                template.scale = scale;
+                return;
+            }
+            _ => println!("Invalid scale '{}'. Please enter a positive number, e.g., '2'.", scale),
+        }
+    }
+}
+
+fn get_valid_output_filename(prompt: &str, extensions: &[&str]) -> String {
+    loop {
+        let filename = get_user_input(prompt);
+        if Path::new(&filename).exists() {
+            println!(
+                "The file '{}' already exists. Please provide a different name.",
+                filename
+            );
+        } else if extensions
+            .iter()
+            .any(|extension| filename.to_lowercase().ends_with(extension))
+            && filename.to_lowercase() != "repeat"
+        {
+            return filename;
+        } else if filename.to_lowercase() == "repeat" {
+            println!("Filename 'Repeat' is not allowed! Please provide a different name.");
+        } else {
+            println!(
+                "Invalid filename. Please ensure the filename ends with {}",
+                extensions.join(" or ")
+            );
+        }
+    }
+}
+
+type DataPoint = (f64, f64);
+
+// Function to read data from CSV
+fn read_data_from_csv_scatter(path: &str) -> Result<(Vec<DataPoint>, Vec<RGBAColor>), Box<dyn Error>> {
+    let table = data_loading::load_table(path)?;
+    let color = color_column_index(&table);
+    let mut columns = (0..table.columns.len()).filter(|index| Some(*index) != color);
+    let (x_index, y_index) = (columns.next().unwrap_or(0), columns.next().unwrap_or(1));
+    let xs = table.floats(x_index)?;
+    let ys = table.floats(y_index)?;
+
+    Ok((xs.into_iter().zip(ys).collect(), read_color_column(&table)?))
+}
+
+fn validate_csv_data_for_scatter(filename: &str) -> Result<(), Box<dyn Error>> {
+    let table = data_loading::load_table(filename)?;
+    let color = color_column_index(&table);
+    let columns: Vec<usize> = (0..table.columns.len()).filter(|index| Some(*index) != color).collect();
+
+    // Check if there are exactly 2 columns besides an optional color column
+    if columns.len() != 2 {
+        return Err(ChartError::invalid(
+            filename,
+            format!(
+                "Expected exactly 2 columns for x and y values (plus an optional 'color' column), found {}.",
+                columns.len()
+            ),
+        )
+        .into());
+    }
+
+    // Validate the x and y columns to ensure they hold numbers, and the colors if there are any
+    table.floats(columns[0])?;
+    table.floats(columns[1])?;
+    read_color_column(&table)?;
+
+    Ok(())
+}
+
+struct ScatterSeries {
+    name: String,
+    points: Vec<DataPoint>,
+    // Per-point colors from a `color` column; empty when the series color is used
+    colors: Vec<RGBAColor>,
+}
+
+// One series per input file, named after the file
+fn read_scatter_series_from_files(files: &[String]) -> Result<Vec<ScatterSeries>, Box<dyn Error>> {
+    let mut series = Vec::new();
+    for file in files {
+        validate_csv_data_for_scatter(file)?;
+        let name = Path::new(file)
+            .file_stem()
+            .map_or_else(|| file.clone(), |stem| stem.to_string_lossy().into_owned());
+        let (points, colors) = read_data_from_csv_scatter(file)?;
+        series.push(ScatterSeries { name, points, colors });
+    }
+    Ok(series)
+}
+
+// One series per distinct value of `group_column`, in order of first appearance.
+// The x and y columns come from the template, or are the first two other columns.
+fn read_grouped_scatter_series(
+    file: &str,
+    group_column: &str,
+    columns: &[String],
+) -> Result<Vec<ScatterSeries>, Box<dyn Error>> {
+    let table = data_loading::load_table(file)?;
+    let group_index = table.column_index(group_column)?;
+    let color_index = color_column_index(&table);
+
+    let (x_index, y_index) = if columns.len() >= 2 {
+        (table.column_index(&columns[0])?, table.column_index(&columns[1])?)
+    } else {
+        let mut others = (0..table.columns.len())
+            .filter(|index| *index != group_index && Some(*index) != color_index);
+        match (others.next(), others.next()) {
+            (Some(x), Some(y)) => (x, y),
+            _ => {
+                return Err(ChartError::invalid(
+                    file,
+                    format!("Expected x and y columns besides the group column '{}'", group_column),
+                )
+                .into())
+            }
+        }
+    };
+
+    let groups = table.strings(group_index)?;
+    let xs = table.floats(x_index)?;
+    let ys = table.floats(y_index)?;
+    let colors = read_color_column(&table)?;
+
+    let mut series: Vec<ScatterSeries> = Vec::new();
+    for (row, ((group, x), y)) in groups.into_iter().zip(xs).zip(ys).enumerate() {
+        let index = match series.iter().position(|s| s.name == group) {
+            Some(index) => index,
+            None => {
+                series.push(ScatterSeries {
+                    name: group,
+                    points: Vec::new(),
+                    colors: Vec::new(),
+                });
+                series.len() - 1
+            }
+        };
+        series[index].points.push((x, y));
+        if let Some(color) = colors.get(row) {
+            series[index].colors.push(*color);
+        }
+    }
+    Ok(series)
+}
+
+#[derive(Default)]
+struct ScatterChart;
+
+impl Chart for ScatterChart {
+    type Data = Vec<ScatterSeries>;
+
+    fn load(&self, template: &Template) -> Result<Self::Data, Box<dyn Error>> {
+        match &template.group_column {
+            Some(group_column) => {
+                if template.inputs.len() != 1 {
+                    let message = format!("a grouping column needs a single input file, found {}", template.inputs.len());
+                    return Err(ChartError::invalid_column(&template.inputs[0], group_column, message).into());
                 }
+                read_grouped_scatter_series(&template.inputs[0], group_column, &template.columns)
             }
-            "3" => {
-                let csv_file_name = get_valid_filename(
-                    "Enter the name of the CSV file (e.g., 'data.csv'):",
-                    ".csv",
-                );
-                let output_file_name = get_valid_output_filename(
-                    "Enter the desired name for the PNG output file (e.g., 'output.png'):",
-                    ".png",
-                );
+            None => read_scatter_series_from_files(&template.inputs),
+        }
+    }
+
+    fn render(&self, series: &Self::Data, template: &Template) -> Result<(), Box<dyn Error>> {
+        with_backend!(template, SCATTER_PLOT_SIZE, |root| draw_scatter_series(
+            &root, series, template
+        ))
+    }
+}
+
+fn draw_scatter_series<DB: DrawingBackend>(
+    root_area: &DrawingArea<DB, Shift>,
+    series: &[ScatterSeries],
+    template: &Template,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    DB::ErrorType: 'static,
+{
+    root_area.fill(&WHITE)?;
+
+    // Axes cover every series with a little padding, rounded out to whole tick steps
+    let all_points = || series.iter().flat_map(|s| s.points.iter());
+    let x_extent = axis::data_extent(all_points().map(|(x, _)| *x)).unwrap_or((0.0, 1.0));
+    let y_extent = axis::data_extent(all_points().map(|(_, y)| *y)).unwrap_or((0.0, 1.0));
+    let (x_extent, x_ticks) = axis::nice(axis::padded(x_extent, 0.05), 10);
+    let (y_extent, y_ticks) = axis::nice(axis::padded(y_extent, 0.05), 10);
+    let x_range = axis::resolve(&template.x_axis, x_extent)?;
+    let y_range = axis::resolve(&template.y_axis, y_extent)?;
+
+    let mut ctx = ChartBuilder::on(root_area)
+        .set_label_area_size(LabelAreaPosition::Left, template.px(40))
+        .set_label_area_size(LabelAreaPosition::Bottom, template.px(40))
+        .caption(&template.title, ("Arial", template.scaled(40.0)).into_font())
+        .build_cartesian_2d(x_range, y_range)?;
+
+    let mut mesh = ctx.configure_mesh();
+    mesh.x_labels(x_ticks).y_labels(y_ticks);
+    if let Some(label) = &template.x_axis.label {
+        mesh.x_desc(label.as_str());
+    }
+    if let Some(label) = &template.y_axis.label {
+        mesh.y_desc(label.as_str());
+    }
+    mesh.label_style(("sans-serif", template.scaled(12.0)))
+        .axis_desc_style(("sans-serif", template.scaled(15.0)))
+        .draw()?;
+
+    let names: Vec<&str> = series.iter().map(|s| s.name.as_str()).collect();
+    let colors = template.colors_for(&names, &[BLUE, RED, GREEN, MAGENTA, CYAN, BLACK]);
+    let (marker, half_square) = (template.px(5), template.px(4));
+    let shapes = [
+        MarkerShape::Triangle,
+        MarkerShape::Circle,
+        MarkerShape::Cross,
+        MarkerShape::Square,
+    ];
+
+    // Draw the data series, each with its own marker and color; a color column overrides it per point
+    for (index, series) in series.iter().enumerate() {
+        let style = ShapeStyle::from(&colors[index % colors.len()]);
+        let point_styles: Vec<ShapeStyle> = (0..series.points.len())
+            .map(|point| series.colors.get(point).map_or(style, ShapeStyle::from))
+            .collect();
+        let points = series.points.iter().copied().zip(point_styles);
+        let name = series.name.as_str();
+
+        match shapes[index % shapes.len()] {
+            MarkerShape::Triangle => {
+                ctx.draw_series(points.map(|(point, point_style)| TriangleMarker::new(point, marker, point_style)))?
+                    .label(name)
+                    .legend(move |(x, y)| TriangleMarker::new((x, y), marker, style));
+            }
+            MarkerShape::Circle => {
+                ctx.draw_series(points.map(|(point, point_style)| Circle::new(point, marker, point_style)))?
+                    .label(name)
+                    .legend(move |(x, y)| Circle::new((x, y), marker, style));
+            }
+            MarkerShape::Cross => {
+                ctx.draw_series(points.map(|(point, point_style)| Cross::new(point, marker, point_style)))?
+                    .label(name)
+                    .legend(move |(x, y)| Cross::new((x, y), marker, style));
+            }
+            MarkerShape::Square => {
+                ctx.draw_series(points.map(|(point, point_style)| {
+                    EmptyElement::at(point)
+                        + Rectangle::new([(-half_square, -half_square), (half_square, half_square)], point_style)
+                }))?
+                .label(name)
+                .legend(move |(x, y)| {
+                    Rectangle::new([(x - half_square, y - half_square), (x + half_square, y + half_square)], style)
+                });
+            }
+        }
+    }
+
+    // Each point's y value, beside the point wherever there is room
+    if template.value_labels {
+        let mut layout = value_label_layout(ctx.plotting_area().get_pixel_range(), template);
+        let style = value_label_style(template);
+        for &(x, y) in series.iter().flat_map(|s| s.points.iter()) {
+            let text = template.number_format.format(y);
+            let sides = [Side::Right, Side::Left, Side::Above, Side::Below];
+            draw_value_label(root_area, &mut layout, &text, ctx.backend_coord(&(x, y)), &sides, &style)?;
+        }
+    }
+
+    ctx.configure_series_labels()
+        .label_font(("sans-serif", template.scaled(15.0)))
+        .legend_area_size(template.px(30))
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+
+    Ok(())
+}
+
+#[derive(Clone, Copy)]
+enum MarkerShape {
+    Triangle,
+    Circle,
+    Cross,
+    Square,
+}
+
+// Define a struct to hold the data from the CSV file
+struct PieChartData {
+    label: String,
+    value: f64,
+    color: RGBAColor,
+    // How far the slice is pulled out of the pie, as a fraction of the radius
+    explode: f64,
+}
+
+impl PieChartData {
+    fn new(label: String, value: f64, color: RGBAColor) -> Self {
+        Self {
+            label,
+            value,
+            color,
+            explode: 0.0,
+        }
+    }
+}
+
+// Distance of a slice exploded with `true` or from the chart options
+const PIE_EXPLODE: f64 = 0.1;
+
+// The index of the `explode` column and the offset it gives each slice
+type PieExplodeColumn = (usize, Vec<f64>);
+
+// The optional `explode` column: true/yes, a fraction of the radius, or empty for slices left in place
+fn read_pie_explode_column(table: &data_loading::Table) -> Result<Option<PieExplodeColumn>, Box<dyn Error>> {
+    let Some(index) = table.columns.iter().position(|column| column.name.eq_ignore_ascii_case("explode")) else {
+        return Ok(None);
+    };
+    let column = table.column_at(index)?;
+    let offsets = table
+        .strings(index)?
+        .into_iter()
+        .enumerate()
+        .map(|(row, text)| match text.trim().to_lowercase().as_str() {
+            "" | "false" | "no" => Ok(0.0),
+            "true" | "yes" => Ok(PIE_EXPLODE),
+            number => number.parse::<f64>().ok().filter(|offset| (0.0..=1.0).contains(offset)).ok_or_else(|| {
+                let expected = "true, false or a number between 0 and 1";
+                Box::<dyn Error>::from(ChartError::conversion(&table.file_path, row + 1, &column.name, &text, expected))
+            }),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Some((index, offsets)))
+}
+
+// Columns holding the label, the value and optionally the RGB channels, in that order
+fn pie_positional_columns(table: &data_loading::Table) -> Result<Vec<usize>, Box<dyn Error>> {
+    let explode = read_pie_explode_column(table)?.map(|(index, _)| index);
+    let color = color_column_index(table);
+    let columns: Vec<usize> = (0..table.columns.len())
+        .filter(|index| Some(*index) != explode && Some(*index) != color)
+        .collect();
+
+    // Label and value, optionally followed by red, green and blue columns unless there is a color column
+    match (columns.len(), color) {
+        (2, _) | (5, None) => Ok(columns),
+        (5, Some(_)) => Err(ChartError::invalid(
+            &table.file_path,
+            "Use either a 'color' column or red, green and blue columns, not both.",
+        )
+        .into()),
+        (count, _) => Err(ChartError::invalid(
+            &table.file_path,
+            format!(
+                "Expected label and value columns, optionally followed by red, green and blue columns or a 'color' column, found {} column(s).",
+                count
+            ),
+        )
+        .into()),
+    }
+}
+
+// Slices without RGB columns get their colors from the template's colors or palette
+fn read_data_from_csv_pie(filename: &str, template: &Template) -> Result<Vec<PieChartData>, Box<dyn Error>> {
+    let table = data_loading::load_table(filename)?;
+    let columns = pie_positional_columns(&table)?;
+    let labels = table.strings(columns[0])?;
+    let values = table.floats(columns[1])?;
+
+    let colors: Vec<RGBAColor> = if columns.len() == 5 {
+        let (reds, greens, blues) = (
+            rgb_channel(&table, columns[2])?,
+            rgb_channel(&table, columns[3])?,
+            rgb_channel(&table, columns[4])?,
+        );
+        (0..table.row_count).map(|row| RGBColor(reds[row], greens[row], blues[row]).to_rgba()).collect()
+    } else if color_column_index(&table).is_some() {
+        read_color_column(&table)?
+    } else {
+        let names: Vec<&str> = labels.iter().map(String::as_str).collect();
+        template.colors_for(&names, &[]).iter().map(|color| color.to_rgba()).collect()
+    };
+
+    let mut data: Vec<PieChartData> = labels
+        .into_iter()
+        .zip(values)
+        .zip(colors)
+        .map(|((label, value), color)| PieChartData::new(label, value, color))
+        .collect();
+    if let Some((_, offsets)) = read_pie_explode_column(&table)? {
+        for (datum, offset) in data.iter_mut().zip(offsets) {
+            datum.explode = offset;
+        }
+    }
+
+    Ok(data)
+}
+
+// The column named `color`, if the table has one
+fn color_column_index(table: &data_loading::Table) -> Option<usize> {
+    table.columns.iter().position(|column| column.name.eq_ignore_ascii_case("color"))
+}
+
+// A color cell holds `#RRGGBB`, `#RRGGBBAA` or a CSS color name; `row` is 0-based
+fn parse_color_cell(file_path: &str, row: usize, column: &str, text: &str) -> Result<RGBAColor, Box<dyn Error>> {
+    palette::parse_color(text).ok_or_else(|| {
+        let expected = "a color as #RRGGBB, #RRGGBBAA or a CSS color name";
+        ChartError::conversion(file_path, row + 1, column, text, expected).into()
+    })
+}
+
+// One color per row from the table's `color` column, or none if it has no such column
+fn read_color_column(table: &data_loading::Table) -> Result<Vec<RGBAColor>, Box<dyn Error>> {
+    let Some(index) = color_column_index(table) else {
+        return Ok(Vec::new());
+    };
+    let column = table.column_at(index)?;
+    table
+        .strings(index)?
+        .iter()
+        .enumerate()
+        .map(|(row, text)| parse_color_cell(&table.file_path, row, &column.name, text))
+        .collect()
+}
+
+// Reads one RGB color channel column, rejecting values outside 0-255
+fn rgb_channel(table: &data_loading::Table, index: usize) -> Result<Vec<u8>, Box<dyn Error>> {
+    let column = table.column_at(index)?;
+    table
+        .integers(index)?
+        .into_iter()
+        .enumerate()
+        .map(|(row, value)| {
+            u8::try_from(value).map_err(|_| {
+                let expected = "an RGB color value between 0 and 255";
+                Box::<dyn Error>::from(ChartError::conversion(
+                    &table.file_path,
+                    row + 1,
+                    &column.name,
+                    &value.to_string(),
+                    expected,
+                ))
+            })
+        })
+        .collect()
+}
+
+fn validate_csv_data_pie(filename: &str) -> Result<(), Box<dyn Error>> {
+    let table = data_loading::load_table(filename)?;
+
+    // Check for label and value columns, plus RGB or color columns
+    let columns = pie_positional_columns(&table)?;
+
+    // Validate the value column to ensure it holds numbers; a negative slice has no size
+    // to draw, and would shrink every other slice's share
+    let values = table.floats(columns[1])?;
+    if let Some(row) = values.iter().position(|value| *value < 0.0) {
+        let column = table.column_at(columns[1])?;
+        return Err(ChartError::conversion(filename, row + 1, &column.name, &column.text(row), "a number of 0 or more").into());
+    }
+
+    // If color columns are provided, validate them too
+    if columns.len() == 5 {
+        for &i in &columns[2..5] {
+            rgb_channel(&table, i)?;
+        }
+    }
+    read_color_column(&table)?;
+
+    Ok(())
+}
+
+#[derive(Default)]
+struct PieChart;
+
+impl Chart for PieChart {
+    type Data = Vec<PieChartData>;
+
+    fn load(&self, template: &Template) -> Result<Self::Data, Box<dyn Error>> {
+        let input_file = &template.inputs[0];
+        validate_csv_data_pie(input_file)?;
+
+        // Read data from CSV file
+        let mut data = read_data_from_csv_pie(input_file, template)?;
+        for label in &template.explode {
+            let datum = data.iter_mut().find(|d| d.label == *label).ok_or_else(|| {
+                ChartError::invalid(input_file, format!("Cannot explode '{}': there is no slice with that label.", label))
+            })?;
+            datum.explode = datum.explode.max(PIE_EXPLODE);
+        }
+        Ok(data)
+    }
+
+    fn validate(&self, data: &Self::Data, template: &Template) -> Result<(), Box<dyn Error>> {
+        let total: f64 = data.iter().map(|d| d.value).sum();
+        if total <= 0.0 {
+            return Err(ChartError::EmptyData {
+                path: template.inputs[0].clone(),
+                reason: Some("the pie values must add up to more than zero".to_string()),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    fn render(&self, data: &Self::Data, template: &Template) -> Result<(), Box<dyn Error>> {
+        with_backend!(template, PIE_CHART_SIZE, |root| draw_pie_slices(&root, data, template))
+    }
+}
+
+// Slices smaller than this share of the pie are labelled outside, at the end of a leader line
+const PIE_SMALL_SLICE: f64 = 0.06;
+
+// One slice's label outside the pie
+struct PieLabel {
+    index: usize,
+    text: String,
+    // Where the slice's middle meets the rim, and where the label would ideally sit
+    rim: (i32, i32),
+    ideal: (i32, i32),
+    y: i32,
+    right_side: bool,
+    leader: bool,
+}
+
+// The annotation for a slice: its share of the total, its value, or both
+fn pie_annotation(datum: &PieChartData, total: f64, annotation: PieAnnotation) -> Option<String> {
+    let percent = format!("{:.1}%", datum.value / total * 100.0);
+    match annotation {
+        PieAnnotation::None => None,
+        PieAnnotation::Percent => Some(percent),
+        PieAnnotation::Value => Some(format_plain_number(datum.value)),
+        PieAnnotation::Both => Some(format!("{} ({})", format_plain_number(datum.value), percent)),
+    }
+}
+
+// Black or white, whichever reads better on `background`
+fn contrasting_text_color(background: &RGBAColor) -> RGBColor {
+    // Translucent colors are seen over the white canvas
+    let RGBAColor(r, g, b, alpha) = *background;
+    let over_white = |channel: u8| channel as f64 * alpha + 255.0 * (1.0 - alpha);
+    let luminance = 0.299 * over_white(r) + 0.587 * over_white(g) + 0.114 * over_white(b);
+    if luminance > 140.0 { BLACK } else { WHITE }
+}
+
+// Moves labels on one side of the pie apart so none overlap, keeping them between `top` and `bottom`
+fn spread_pie_labels(labels: &mut [&mut PieLabel], line_height: i32, top: i32, bottom: i32) {
+    labels.sort_by_key(|label| label.ideal.1);
+    let mut next_free = top;
+    for label in labels.iter_mut() {
+        label.y = label.ideal.1.max(next_free);
+        next_free = label.y + line_height;
+    }
+    // Labels pushed past the bottom move back up, pushing their neighbours with them
+    let mut last_free = bottom;
+    for label in labels.iter_mut().rev() {
+        label.y = label.y.min(last_free);
+        last_free = label.y - line_height;
+    }
+}
+
+fn draw_pie_slices<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    data: &[PieChartData],
+    template: &Template,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    root.fill(&WHITE)?;
+
+    let size = root.dim_in_pixel();
+    let (width, height) = (size.0 as i32, size.1 as i32);
+    let gap = template.px(10);
+    let total: f64 = data.iter().map(|d| d.value).sum();
+
+    // Draw the title
+    let mut top = gap;
+    if !template.title.is_empty() {
+        let title_style = TextStyle::from(("sans-serif", template.scaled(24.0)).into_font())
+            .color(&BLACK)
+            .pos(Pos::new(HPos::Center, VPos::Top));
+        root.draw(&Text::new(template.title.clone(), (width / 2, gap), title_style.clone()))?;
+        top += root.estimate_text_size(&template.title, &title_style)?.1 as i32 + gap;
+    }
 
-                let chart_title = get_user_input("Enter the title for the chart:");
-                if let Err(err) = draw_pie_chart_to_png(&csv_file_name, &output_file_name,&chart_title) {
-                    eprintln!("Error drawing the pie chart: {}", err);
-                }
-            }
-            "4" => {
-                let csv_file_name = get_valid_filename(
-                    "Enter the name of the CSV file (e.g., 'data.csv'):",
-                    ".csv",
-                );
-                let output_file_name = get_valid_output_filename(
-                    "Enter the desired name for the PNG output file (e.g., 'output.png'):",
-                    ".png",
-                );
+    let mut right = width - gap;
+    if template.legend {
+        right -= draw_pie_legend(root, data, template, right, top)? + gap;
+    }
+
+    // Large slices carry their annotation inside; small ones add it to their outside label
+    let label_style = TextStyle::from(("sans-serif", template.scaled(15.0)).into_font()).color(&BLACK);
+    let mut labels = Vec::new();
+    for datum in data {
+        let annotation = pie_annotation(datum, total, template.pie_annotation);
+        let small = datum.value / total < PIE_SMALL_SLICE;
+        let text = match (&annotation, small) {
+            (Some(annotation), true) => format!("{} {}", datum.label, annotation),
+            _ => datum.label.clone(),
+        };
+        labels.push((text, small));
+    }
+    let mut label_width = 0;
+    let mut line_height = 0;
+    for (text, _) in &labels {
+        let (text_width, text_height) = root.estimate_text_size(text, &label_style)?;
+        label_width = label_width.max(text_width as i32);
+        line_height = line_height.max(text_height as i32 + template.px(2));
+    }
 
-                let chart_title = get_user_input("Enter the title for the chart:");
+    // The pie fills the space left once the labels on both sides fit
+    let bottom = height - gap;
+    let center = ((gap + right) / 2, (top + bottom) / 2);
+    let space = ((right - gap) / 2 - label_width - 3 * gap)
+        .min((bottom - top) / 2 - line_height - 2 * gap)
+        .max(template.px(20)) as f64;
+    // Exploded slices need room to move out
+    let max_explode = data.iter().map(|d| d.explode).fold(0.0, f64::max);
+    let radius = space / (1.0 + max_explode);
+    let inner_radius = template.donut.map_or(0.0, |fraction| radius * fraction);
+    let at = |origin: (i32, i32), angle: f64, distance: f64| {
+        (
+            (origin.0 as f64 + distance * angle.cos()).round() as i32,
+            (origin.1 as f64 + distance * angle.sin()).round() as i32,
+        )
+    };
 
-                if let Err(err) = draw_line_and_area(&csv_file_name, &output_file_name, &chart_title) {
-                    eprintln!("Error drawing the line and area chart: {}", err);
-                }
-            }
-            "5" => { 
-                let csv_file_name = get_valid_filename("Enter the name of the CSV file for radar chart (e.g., 'radar_data.csv'):", ".csv");
-                let output_file_name = get_valid_output_filename("Enter the desired name for the SVG output file (e.g., 'radar_chart.png):", ".png");
-                let chart_title = get_user_input("Enter the title for the chart:");
-
-                match read_from_csv_radar(&csv_file_name) {
-                    Ok(data) => {
-                        if let Err(e) = draw_radar_chart(&data, &output_file_name) {
-                            eprintln!("Error plotting radar chart: {}", e);
-                        } else {
-                            println!("Radar chart generated successfully!");
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("Error loading CSV file: {}", e);
-                    }
-                }
+    let mut start_angle = 0.0;
+    let mut outside = Vec::new();
+    for (index, pie_data) in data.iter().enumerate() {
+        let end_angle = start_angle + pie_data.value / total * 2.0 * std::f64::consts::PI;
+        let mid_angle = (start_angle + end_angle) / 2.0;
+        let origin = at(center, mid_angle, pie_data.explode * radius);
+
+        // The outer arc, then back along the inner arc of a donut or through the center of a pie
+        let arc = |distance: f64| {
+            (0..=100).map(move |p| at(origin, start_angle + (end_angle - start_angle) * p as f64 / 100.0, distance))
+        };
+        let points: Vec<_> = if inner_radius > 0.0 {
+            arc(radius).chain(arc(inner_radius).rev()).collect()
+        } else {
+            std::iter::once(origin).chain(arc(radius)).collect()
+        };
+        root.draw(&Polygon::new(points, pie_data.color.filled()))?;
+
+        let (text, small) = &labels[index];
+        if !small {
+            if let Some(annotation) = pie_annotation(pie_data, total, template.pie_annotation) {
+                let text_color = contrasting_text_color(&pie_data.color);
+                let style = label_style.color(&text_color).pos(Pos::new(HPos::Center, VPos::Center));
+                let distance = if inner_radius > 0.0 { (radius + inner_radius) / 2.0 } else { radius * 0.65 };
+                root.draw(&Text::new(annotation, at(origin, mid_angle, distance), style))?;
             }
-            
-            _ => unreachable!(),
         }
+
+        let distance = if *small { radius + 3.0 * gap as f64 } else { radius + gap as f64 };
+        outside.push(PieLabel {
+            index,
+            text: text.clone(),
+            rim: at(origin, mid_angle, radius),
+            ideal: at(origin, mid_angle, distance),
+            y: 0,
+            right_side: mid_angle.cos() >= 0.0,
+            leader: *small,
+        });
+
+        start_angle = end_angle;
     }
-}
 
-fn get_valid_filename(prompt: &str, extension: &str) -> String {
-    loop {
-        let filename = get_user_input(prompt);
-        if filename.to_lowercase().ends_with(extension) && filename.to_lowercase() != "repeat" {
-            return filename;
-        } else if filename.to_lowercase() == "repeat" {
-            println!("Filename 'Repeat' is not allowed! Please provide a different name.");
+    // Keep labels on each side from overlapping; any label moved off its slice gets a leader line
+    let half_line = line_height / 2;
+    let (mut right_labels, mut left_labels): (Vec<&mut PieLabel>, Vec<&mut PieLabel>) =
+        outside.iter_mut().partition(|label| label.right_side);
+    spread_pie_labels(&mut right_labels, line_height, top + half_line, bottom - half_line);
+    spread_pie_labels(&mut left_labels, line_height, top + half_line, bottom - half_line);
+
+    for label in &outside {
+        let moved = (label.y - label.ideal.1).abs() > template.px(2);
+        let side = if label.right_side { 1 } else { -1 };
+        // Moved labels stay clear of the pie at their new height
+        let reach = radius * (1.0 + max_explode) + 2.0 * gap as f64;
+        let dy = (label.y - center.1) as f64;
+        let clear = (reach * reach - dy * dy).max(0.0).sqrt() as i32;
+        let x = if moved {
+            center.0 + side * clear.max((label.ideal.0 - center.0).abs())
         } else {
-            println!(
-                "Invalid filename. Please ensure the filename ends with {}",
-                extension
-            );
+            label.ideal.0
+        };
+
+        if label.leader || moved {
+            let color = data[label.index].color;
+            let line_style = color.stroke_width(template.px(1).max(1) as u32);
+            root.draw(&PathElement::new(vec![label.rim, (x, label.y)], line_style))?;
         }
+        let h_pos = if label.right_side { HPos::Left } else { HPos::Right };
+        let text_x = x + side * template.px(3);
+        let style = label_style.pos(Pos::new(h_pos, VPos::Center));
+        root.draw(&Text::new(label.text.clone(), (text_x, label.y), style))?;
     }
-}
 
-use std::path::Path;
+    if inner_radius > 0.0 {
+        let text = template
+            .center_text
+            .as_deref()
+            .unwrap_or("{total}")
+            .replace("{total}", &format_plain_number(total));
+        let style = TextStyle::from(("sans-serif", template.scaled(20.0)).into_font())
+            .color(&BLACK)
+            .pos(Pos::new(HPos::Center, VPos::Center));
+        root.draw(&Text::new(text, center, style))?;
+    }
 
-fn get_valid_output_filename(prompt: &str, extension: &str) -> String {
-    loop {
-        let filename = get_user_input(prompt);
-        if Path::new(&filename).exists() {
-            println!(
-                "The file '{}' already exists. Please provide a different name.",
-                filename
-            );
-        } else if filename.to_lowercase().ends_with(extension)
-            && filename.to_lowercase() != "repeat"
-        {
-            return filename;
-        } else if filename.to_lowercase() == "repeat" {
-            println!("Filename 'Repeat' is not allowed! Please provide a different name.");
-        } else {
-            println!(
-                "Invalid filename. Please ensure the filename ends with {}",
-                extension
-            );
-        }
+    Ok(())
+}
+
+// Slice colors and labels in a column at the right edge; returns the width it takes
+fn draw_pie_legend<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    data: &[PieChartData],
+    template: &Template,
+    right: i32,
+    top: i32,
+) -> Result<i32, Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let style = TextStyle::from(("sans-serif", template.scaled(15.0)).into_font())
+        .color(&BLACK)
+        .pos(Pos::new(HPos::Left, VPos::Center));
+    let mut text_width = 0;
+    for datum in data {
+        text_width = text_width.max(root.estimate_text_size(&datum.label, &style)?.0 as i32);
+    }
+    let (row_height, swatch) = (template.px(20), template.px(6));
+    let width = text_width + 3 * swatch;
+    let left = right - width;
+
+    for (index, datum) in data.iter().enumerate() {
+        let y = top + row_height / 2 + index as i32 * row_height;
+        root.draw(&Rectangle::new([(left, y - swatch), (left + 2 * swatch, y + swatch)], datum.color.filled()))?;
+        root.draw(&Text::new(datum.label.clone(), (left + 3 * swatch, y), style.clone()))?;
     }
+
+    Ok(width)
 }
 
-use std::error::Error;
-use std::fs::File;
-type DataPoint = (i32, i32);
+fn validate_csv_data_for_line_area(filename: &str, date_format: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let table = data_loading::load_table(filename)?;
 
-// Function to read data from CSV
-fn read_data_from_csv_scatter(path: &str) -> Result<Vec<DataPoint>, Box<dyn Error>> {
-    let mut rdr = csv::Reader::from_reader(File::open(path)?);
-    let mut data = Vec::new();
+    // Check if there is an x column and at least one value column
+    if table.columns.len() < 2 {
+        return Err(ChartError::invalid(
+            filename,
+            format!("Expected an x column and at least one value column, found {} column(s).", table.columns.len()),
+        )
+        .into());
+    }
 
-    for result in rdr.records() {
-        let record = result?;
-        let x: i32 = record[0].parse()?;
-        let y: i32 = record[1].parse()?;
-        data.push((x, y));
+    // Validate the x column as numbers or dates, and the value columns as numbers
+    read_line_area_x_column(&table, 0, date_format)?;
+    for index in 1..table.columns.len() {
+        table.floats(index)?;
     }
 
-    Ok(data)
+    Ok(())
 }
 
-fn validate_csv_data_for_scatter(filename: &str) -> Result<(), Box<dyn Error>> {
-    let file = File::open(filename)?;
-    let mut rdr = Reader::from_reader(file);
+#[derive(Clone)]
+struct LineAreaSeries {
+    name: String,
+    values: Vec<f64>,
+}
 
-    for (line, result) in rdr.records().enumerate() {
-        let record = result?;
+// One x column shared by every value series, sorted by x
+#[derive(Clone)]
+struct LineAreaData {
+    xs: Vec<f64>,
+    series: Vec<LineAreaSeries>,
+    // Whether the x values are timestamps (seconds since the Unix epoch)
+    is_time: bool,
+}
 
-        // Check if there are exactly 2 columns
-        if record.len() != 2 {
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                format!("Line {}: Expected exactly 2 columns for x and y values.", line + 1),
-            )));
-        }
+// The first of `columns` is the x column and the rest are value columns;
+// without `columns`, the first CSV column is x and every other column is a series
+fn read_data_from_csv_line_area(
+    filename: &str,
+    columns: &[String],
+    date_format: Option<&str>,
+) -> Result<LineAreaData, Box<dyn std::error::Error>> {
+    let table = data_loading::load_table(filename)?;
+    let indices = if columns.is_empty() {
+        (0..table.columns.len()).collect::<Vec<_>>()
+    } else {
+        columns
+            .iter()
+            .map(|name| table.column_index(name))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    if indices.len() < 2 {
+        return Err(ChartError::invalid(filename, "Line charts need an x column and at least one value column.").into());
+    }
+
+    let (xs, is_time) = read_line_area_x_column(&table, indices[0], date_format)?;
+    let mut series = Vec::new();
+    for &index in &indices[1..] {
+        series.push(LineAreaSeries {
+            name: table.column_at(index)?.name.clone(),
+            values: table.floats(index)?,
+        });
+    }
+
+    // Rows may come in any order, but lines are drawn from left to right
+    let mut order: Vec<usize> = (0..xs.len()).collect();
+    order.sort_by(|a, b| xs[*a].total_cmp(&xs[*b]));
+    let xs = order.iter().map(|row| xs[*row]).collect();
+    for s in &mut series {
+        s.values = order.iter().map(|row| s.values[*row]).collect();
+    }
+
+    Ok(LineAreaData { xs, series, is_time })
+}
 
-        // Validate the x and y columns to ensure they can be parsed as i32
-        if record[0].trim().parse::<i32>().is_err() {
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                format!("Line {}: Invalid x value. Expected an integer.", line + 1),
-            )));
+// The x column holds numbers, ISO-8601 dates, or dates in `date_format` when one is given
+fn read_line_area_x_column(
+    table: &data_loading::Table,
+    index: usize,
+    date_format: Option<&str>,
+) -> Result<(Vec<f64>, bool), Box<dyn Error>> {
+    let timestamps = match date_format {
+        Some(format) => table.datetimes_with_format(index, format)?,
+        None if table.column_at(index)?.column_type() == data_loading::ColumnType::DateTime => {
+            table.datetimes(index)?
         }
+        None => return Ok((table.floats(index)?, false)),
+    };
+    Ok((timestamps.into_iter().map(|t| t as f64).collect(), true))
+}
+
+#[derive(Default)]
+struct LineAreaChart;
 
-        if record[1].trim().parse::<i32>().is_err() {
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                format!("Line {}: Invalid y value. Expected an integer.", line + 1),
-            )));
+impl Chart for LineAreaChart {
+    type Data = LineAreaData;
+
+    fn load(&self, template: &Template) -> Result<Self::Data, Box<dyn Error>> {
+        let input_file = &template.inputs[0];
+        let date_format = template.date_format.as_deref();
+        if template.columns.is_empty() {
+            validate_csv_data_for_line_area(input_file, date_format)?;
         }
+        read_data_from_csv_line_area(input_file, &template.columns, date_format)
     }
 
-    Ok(())
+    fn render(&self, data: &Self::Data, template: &Template) -> Result<(), Box<dyn Error>> {
+        draw_line_and_area(data.clone(), template)
+    }
 }
 
+// The y values each series is drawn at: its own values, or when stacked,
+// the running total of the series before it
+fn line_tops(data: &LineAreaData, mode: LineMode) -> Vec<Vec<f64>> {
+    match mode {
+        LineMode::Stacked => data
+            .series
+            .iter()
+            .scan(vec![0.0; data.xs.len()], |total, series| {
+                for (sum, value) in total.iter_mut().zip(&series.values) {
+                    *sum += value;
+                }
+                Some(total.clone())
+            })
+            .collect(),
+        LineMode::Line | LineMode::Area => data.series.iter().map(|s| s.values.clone()).collect(),
+    }
+}
 
-fn draw_scatter_plot(
-    input_file1: &str,
-    input_file2: &str,
-    output_file: &str,
-    chart_title: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    validate_csv_data_for_scatter(input_file1)?;
-    validate_csv_data_for_scatter(input_file2)?;
-    let data1 = read_data_from_csv_scatter(input_file1)?;
-    let data2 = read_data_from_csv_scatter(input_file2)?;
+fn draw_line_and_area(mut data: LineAreaData, template: &Template) -> Result<(), Box<dyn std::error::Error>> {
+    let tops = line_tops(&data, template.line_mode);
+
+    // Determine the x and y ranges from the data; areas keep their zero baseline in view
+    let x_extent = axis::data_extent(data.xs.iter().copied()).unwrap_or((0.0, 10.0));
+    let baseline = match template.line_mode {
+        LineMode::Line => None,
+        LineMode::Area | LineMode::Stacked => Some(0.0),
+    };
+    let (y_min, y_max) = axis::data_extent(tops.iter().flatten().copied().chain(baseline)).unwrap_or((0.0, 50.0));
+    let (padded_min, padded_max) = axis::padded((y_min, y_max), 0.05);
+    let y_extent = match baseline {
+        Some(zero) => (if y_min < zero { padded_min } else { zero }, if y_max > zero { padded_max } else { zero }),
+        None => (padded_min, padded_max),
+    };
+    let x_extent = if data.is_time {
+        axis::padded_time(x_extent)
+    } else {
+        axis::padded(x_extent, 0.0)
+    };
+    let mut x_range = axis::resolve(&template.x_axis, x_extent)?;
+    let y_range = axis::resolve(&template.y_axis, y_extent)?;
+
+    // Dates are plotted in whole days, hours, ... counted from the start of the range
+    let time_axis = if data.is_time {
+        let time_axis = axis::TimeAxis::new((x_range.start, x_range.end));
+        for x in &mut data.xs {
+            *x = time_axis.to_axis(*x);
+        }
+        x_range = time_axis.to_axis(x_range.start)..time_axis.to_axis(x_range.end);
+        Some(time_axis)
+    } else {
+        None
+    };
+
+    with_backend!(template, LINE_AREA_CHART_SIZE, |root| draw_area_series(
+        &root,
+        &data,
+        &tops,
+        x_range,
+        y_range,
+        time_axis,
+        template
+    ))
+}
 
-    let root_area = BitMapBackend::new(output_file, (600, 400)).into_drawing_area();
+fn draw_area_series<DB: DrawingBackend>(
+    root_area: &DrawingArea<DB, Shift>,
+    data: &LineAreaData,
+    tops: &[Vec<f64>],
+    x_range: std::ops::Range<f64>,
+    y_range: std::ops::Range<f64>,
+    time_axis: Option<axis::TimeAxis>,
+    template: &Template,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    DB::ErrorType: 'static,
+{
     root_area.fill(&WHITE)?;
+    let x_label_count = time_axis.map(|time_axis| time_axis.label_count(&x_range));
+
+    let mut ctx = ChartBuilder::on(root_area)
+    .set_label_area_size(LabelAreaPosition::Left, template.px(40))
+    .set_label_area_size(LabelAreaPosition::Bottom, template.px(40))
+    .caption(&template.title, ("sans-serif", template.scaled(40.0)))
+    .build_cartesian_2d(x_range, y_range)?
+    ;
+
+    let date_formatter = |x: &f64| time_axis.map_or_else(String::new, |time_axis| time_axis.label(*x));
+    let mut mesh = ctx.configure_mesh();
+    if let Some(count) = x_label_count {
+        mesh.x_labels(count).x_label_formatter(&date_formatter);
+    }
+    if let Some(label) = &template.x_axis.label {
+        mesh.x_desc(label.as_str());
+    }
+    if let Some(label) = &template.y_axis.label {
+        mesh.y_desc(label.as_str());
+    }
+    mesh.label_style(("sans-serif", template.scaled(12.0)))
+        .axis_desc_style(("sans-serif", template.scaled(15.0)))
+        .draw()?;
 
-    let mut ctx = ChartBuilder::on(&root_area)
-        .set_label_area_size(LabelAreaPosition::Left, 40)
-        .set_label_area_size(LabelAreaPosition::Bottom, 40)
-        .caption(chart_title, ("Arial", 40).into_font())
-        .build_cartesian_2d(-10..50, -10..50)?;
+    let names: Vec<&str> = data.series.iter().map(|s| s.name.as_str()).collect();
+    let colors = template.colors_for(&names, &[RED, BLUE, GREEN, MAGENTA, CYAN, BLACK]);
+    let mut bottom = vec![0.0; data.xs.len()];
+    let (line_width, swatch) = (template.px(2) as u32, template.px(5));
+
+    for (index, (series, top)) in data.series.iter().zip(tops).enumerate() {
+        let color = colors[index % colors.len()];
+        let points = data.xs.iter().copied().zip(top.iter().copied());
+
+        let annotation = match template.line_mode {
+            LineMode::Line => ctx.draw_series(LineSeries::new(points, color.stroke_width(line_width)))?,
+            LineMode::Area => ctx.draw_series(AreaSeries::new(points, 0.0, color.mix(0.2)).border_style(color.stroke_width(line_width)))?,
+            LineMode::Stacked => {
+                // The band between this series' total and the one below it
+                let band: Vec<(f64, f64)> = points
+                    .clone()
+                    .chain(data.xs.iter().copied().zip(bottom.iter().copied()).rev())
+                    .collect();
+                ctx.draw_series(std::iter::once(Polygon::new(band, color.mix(0.6).filled())))?;
+                ctx.draw_series(LineSeries::new(points, color.stroke_width(line_width)))?
+            }
+        };
+        annotation
+            .label(series.name.as_str())
+            .legend(move |(x, y)| Rectangle::new([(x, y - swatch), (x + 2 * swatch, y + swatch)], color.filled()));
+        bottom = top.clone();
+    }
 
-    ctx.configure_mesh().draw()?;
+    // Each series' own value at its vertices, also when stacked on the series below
+    if template.value_labels {
+        let mut layout = value_label_layout(ctx.plotting_area().get_pixel_range(), template);
+        let style = value_label_style(template);
+        for (series, top) in data.series.iter().zip(tops) {
+            for ((x, y), value) in data.xs.iter().zip(top).zip(&series.values) {
+                let text = template.number_format.format(*value);
+                let sides = [Side::Above, Side::Below, Side::Right, Side::Left];
+                draw_value_label(root_area, &mut layout, &text, ctx.backend_coord(&(*x, *y)), &sides, &style)?;
+            }
+        }
+    }
 
-    // Draw the data series
-    ctx.draw_series(
-        data1
-            .iter()
-            .map(|point| TriangleMarker::new(*point, 5, &BLUE)),
-    )?;
-    ctx.draw_series(data2.iter().map(|point| Circle::new(*point, 5, &RED)))
-        .unwrap();
+    ctx.configure_series_labels()
+        .label_font(("sans-serif", template.scaled(15.0)))
+        .legend_area_size(template.px(30))
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
 
     Ok(())
 }
 
-// Define a struct to hold the data from the CSV file
-struct PieChartData {
-    label: String,
-    value: f64,
-    color: RGBColor,
+struct RadarSeries {
+    name: String,
+    values: Vec<f64>,
 }
 
-impl PieChartData {
-    fn new(label: String, value: f64, color: RGBColor) -> Self {
-        Self {
-            label,
-            value,
-            color,
-        }
-    }
+// The labels around the radar and one value per label for every series
+struct RadarData {
+    labels: Vec<String>,
+    series: Vec<RadarSeries>,
 }
 
-fn read_data_from_csv_pie(filename: &str) -> Result<Vec<PieChartData>, Box<dyn Error>> {
-    let mut data = Vec::new();
-    let file = File::open(filename)?;
-    let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(file);
-
-    for (line, result) in rdr.records().enumerate() {
-        let record = result?;
-        let label = record[0].to_string();
-
-        let value: f64 = record
-            .get(1)
-            .ok_or(format!("Missing value on line {}", line + 2))?
-            .trim()
-            .parse()
-            .map_err(|e| format!("Error on line {}: {}", line + 2, e))?;
-
-        let mut rng = rand::thread_rng();
-
-        let color = match record.len() {
-            2..=4 => RGBColor(
-                rng.gen_range(0..=255),
-                rng.gen_range(0..=255),
-                rng.gen_range(0..=255),
-            ), // Random color if not specified
-            5 => RGBColor(
-                record[2]
-                    .trim()
-                    .parse()
-                    .map_err(|e| format!("Error on line {}: {}", line + 2, e))?,
-                record[3]
-                    .trim()
-                    .parse()
-                    .map_err(|e| format!("Error on line {}: {}", line + 2, e))?,
-                record[4]
-                    .trim()
-                    .parse()
-                    .map_err(|e| format!("Error on line {}: {}", line + 2, e))?,
-            ),
-            _ => {
-                return Err(Box::new(std::io::Error::new(
-                    std::io::ErrorKind::InvalidInput,
-                    "Unsupported number of columns",
-                )))
+// Wide format: a label column followed by one value column per series.
+// Long format (with `series_column`): one row per series and label, holding a label and a value.
+fn read_from_csv_radar(
+    file_path: &str,
+    series_column: Option<&str>,
+    columns: &[String],
+) -> Result<RadarData, Box<dyn Error>> {
+    let table = data_loading::load_table(file_path)?;
+    let series_index = series_column.map(|name| table.column_index(name)).transpose()?;
+    let indices = if columns.is_empty() {
+        (0..table.columns.len())
+            .filter(|index| Some(*index) != series_index)
+            .collect::<Vec<_>>()
+    } else {
+        columns
+            .iter()
+            .map(|name| table.column_index(name))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    if indices.len() < 2 {
+        return Err(ChartError::invalid(file_path, "Expected a label column and at least one value column").into());
+    }
+    let labels = table.strings(indices[0])?;
+
+    let Some(series_index) = series_index else {
+        let mut series = Vec::new();
+        for &index in &indices[1..] {
+            series.push(RadarSeries {
+                name: table.column_at(index)?.name.clone(),
+                values: table.floats(index)?,
+            });
+        }
+        return Ok(RadarData { labels, series });
+    };
+
+    // Labels and series appear in the order they are first seen
+    let names = table.strings(series_index)?;
+    let values = table.floats(indices[1])?;
+    let mut axes: Vec<String> = Vec::new();
+    for label in &labels {
+        if !axes.contains(label) {
+            axes.push(label.clone());
+        }
+    }
+    let mut series: Vec<RadarSeries> = Vec::new();
+    for (row, ((name, label), value)) in names.into_iter().zip(&labels).zip(values).enumerate() {
+        let axis = axes.iter().position(|axis| axis == label).unwrap();
+        let index = match series.iter().position(|s| s.name == name) {
+            Some(index) => index,
+            None => {
+                series.push(RadarSeries {
+                    name,
+                    values: vec![f64::NAN; axes.len()],
+                });
+                series.len() - 1
             }
         };
+        if !series[index].values[axis].is_nan() {
+            return Err(ChartError::InvalidData {
+                path: file_path.to_string(),
+                row: Some(row + 1),
+                column: None,
+                message: format!("series '{}' already has a value for '{}'", series[index].name, label),
+            }
+            .into());
+        }
+        series[index].values[axis] = value;
+    }
+    for s in &series {
+        if let Some(axis) = s.values.iter().position(|value| value.is_nan()) {
+            let message = format!("series '{}' has no value for '{}'", s.name, axes[axis]);
+            return Err(ChartError::invalid(file_path, message).into());
+        }
+    }
+
+    Ok(RadarData { labels: axes, series })
+}
+
+const RADAR_RINGS: usize = 4;
+const RADAR_MARGIN: i32 = 10;
+// Space between the outer ring and the labels
+const RADAR_LABEL_GAP: i32 = 10;
+const RADAR_MIN_RADIUS: i32 = 20;
+
+// Alignment for a label at `anchor`, just outside the radar at `angle`, so the text extends
+// away from the chart; and the rectangle the text then covers
+fn radar_label_placement(anchor: (f32, f32), angle: f32, text_size: (u32, u32)) -> (Pos, [(i32, i32); 2]) {
+    let (width, height) = (text_size.0 as i32, text_size.1 as i32);
+    let (x, y) = (anchor.0 as i32, anchor.1 as i32);
+    let (h_pos, left) = match angle.cos() {
+        cos if cos > 0.1 => (HPos::Left, x),
+        cos if cos < -0.1 => (HPos::Right, x - width),
+        _ => (HPos::Center, x - width / 2),
+    };
+    let (v_pos, top) = match angle.sin() {
+        sin if sin > 0.1 => (VPos::Bottom, y - height),
+        sin if sin < -0.1 => (VPos::Top, y),
+        _ => (VPos::Center, y - height / 2),
+    };
+    (Pos::new(h_pos, v_pos), [(left, top), (left + width, top + height)])
+}
 
-        let pie_data = PieChartData::new(label, value, color);
-        data.push(pie_data);
+// Value range of every radar label: its [axis_limits] entry if it has one, otherwise the
+// y axis limits over a range fitted to all values, or to the label's own values with a per-axis scale
+fn radar_ranges(data: &RadarData, template: &Template) -> Result<Vec<std::ops::Range<f64>>, Box<dyn Error>> {
+    let all_values = data.series.iter().flat_map(|s| s.values.iter().copied());
+    let global_extent = axis::data_extent(all_values);
+    let mut ranges = Vec::new();
+    for (index, label) in data.labels.iter().enumerate() {
+        if let Some(limit) = template.radar_limits.iter().find(|limit| limit.label == *label) {
+            ranges.push(limit.min..limit.max);
+            continue;
+        }
+        let extent = match template.radar_scale {
+            RadarScale::Global => global_extent,
+            RadarScale::PerAxis => axis::data_extent(data.series.iter().map(|s| s.values[index])),
+        };
+        // Rings count up from zero unless the data goes below it
+        let (min, max) = extent.unwrap_or((0.0, 1.0));
+        let extent = axis::nice_divisions((min.min(0.0), max), RADAR_RINGS);
+        ranges.push(axis::resolve(&template.y_axis, extent)?);
     }
+    Ok(ranges)
+}
 
-    Ok(data)
+// Numbers without trailing zeros, e.g. "2.5" or "100"
+fn format_plain_number(value: f64) -> String {
+    let text = format!("{:.3}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" { "0".to_string() } else { text.to_string() }
 }
 
-fn validate_csv_data_pie(filename: &str) -> Result<(), Box<dyn Error>> {
-    let file = File::open(filename)?;
-    let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(file);
-
-    for (line, result) in rdr.records().enumerate() {
-        let record = result?;
-
-        // Check if there are at least 2 columns (label and value)
-        if record.len() < 2 {
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                format!("Line {}: Expected at least 2 columns for label and value.", line + 2),
-            )));
-        }
-
-        // Validate the value column to ensure it can be parsed as f64
-        if record[1].trim().parse::<f64>().is_err() {
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                format!("Line {}: Invalid value. Expected a number.", line + 2),
-            )));
-        }
-
-        // If color columns are provided, validate them too
-        if record.len() >= 5 {
-            for i in 2..5 {
-                if record[i].trim().parse::<u8>().is_err() {
-                    return Err(Box::new(std::io::Error::new(
-                        std::io::ErrorKind::InvalidInput,
-                        format!("Line {}: Invalid RGB color value at column {}. Expected a number between 0 and 255.", line + 2, i + 1),
-                    )));
-                }
+#[derive(Default)]
+struct RadarChart;
+
+impl Chart for RadarChart {
+    type Data = RadarData;
+
+    fn load(&self, template: &Template) -> Result<Self::Data, Box<dyn Error>> {
+        read_from_csv_radar(&template.inputs[0], template.group_column.as_deref(), &template.columns)
+    }
+
+    fn validate(&self, data: &Self::Data, template: &Template) -> Result<(), Box<dyn Error>> {
+        if data.labels.len() < 3 {
+            let message = format!("A radar chart needs at least 3 labels, found {}", data.labels.len());
+            return Err(ChartError::invalid(&template.inputs[0], message).into());
+        }
+        for limit in &template.radar_limits {
+            if !data.labels.contains(&limit.label) {
+                let message = format!(
+                    "the axis limit for '{}' does not match any radar label. Labels: {}",
+                    limit.label,
+                    data.labels.join(", ")
+                );
+                return Err(ChartError::invalid(&template.inputs[0], message).into());
             }
         }
+        // Surfaces y axis limits that leave no range before anything is drawn
+        radar_ranges(data, template)?;
+        Ok(())
     }
 
-    Ok(())
+    fn render(&self, data: &Self::Data, template: &Template) -> Result<(), Box<dyn Error>> {
+        with_backend!(template, RADAR_CHART_SIZE, |root| draw_radar_polygon(&root, data, template))
+    }
 }
 
-
-fn draw_pie_chart_to_png(input_file: &str, output_file: &str, chart_title: &str) -> Result<(), Box<dyn Error>> {
-    validate_csv_data_pie(&input_file)?;
-    
-    let root = BitMapBackend::new(output_file, (350, 350)).into_drawing_area();
+fn draw_radar_polygon<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    data: &RadarData,
+    template: &Template,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let ranges = radar_ranges(data, template)?;
+    let size = root.dim_in_pixel();
+    let (width, height) = (size.0 as i32, size.1 as i32);
     root.fill(&WHITE)?;
 
-    let center = (175, 175);  // Adjusted center
-    let radius = 100.0;
-
-    // Read data from CSV file
-    let mut data = read_data_from_csv_pie(input_file)?;
+    // The title goes across the top and the radar is centered in the space below it
+    let margin = template.px(RADAR_MARGIN);
+    let label_gap = template.px(RADAR_LABEL_GAP) as f32;
+    let mut top = margin;
+    if !template.title.is_empty() {
+        let title_style = TextStyle::from(("sans-serif", template.scaled(24.0)).into_font())
+            .color(&BLACK)
+            .pos(Pos::new(HPos::Center, VPos::Top));
+        root.draw(&Text::new(template.title.clone(), (width / 2, margin), title_style.clone()))?;
+        top += root.estimate_text_size(&template.title, &title_style)?.1 as i32 + margin;
+    }
 
-    // Adjust the values in the data vector to make the total 100
-    let total: f64 = data.iter().map(|d| d.value).sum();
-    let adjustment_factor = 100.0 / total;
-    for datum in &mut data {
-        datum.value *= adjustment_factor;
+    let center = (width as f32 / 2.0, (height + top) as f32 / 2.0);
+    let step_angle = 2.0 * std::f32::consts::PI / data.labels.len() as f32;
+    let offset = |index: usize, radius: f32| {
+        let angle = step_angle * index as f32;
+        (center.0 + radius * angle.cos(), center.1 - radius * angle.sin())
+    };
+    let point_at = |index: usize, radius: f32| {
+        let (x, y) = offset(index, radius);
+        (x as i32, y as i32)
+    };
+
+    // Shrink the radar until every label fits inside the image
+    let label_style = TextStyle::from(("sans-serif", template.scaled(15.0)).into_font()).color(&BLACK);
+    let label_sizes = data
+        .labels
+        .iter()
+        .map(|label| root.estimate_text_size(label, &label_style))
+        .collect::<Result<Vec<_>, _>>()?;
+    let label_shift = |index: usize, radius: f32| {
+        let (_, [(left, label_top), (right, bottom)]) = radar_label_placement(
+            offset(index, radius + label_gap),
+            step_angle * index as f32,
+            label_sizes[index],
+        );
+        // Labels too long to fit at any radius are pushed back inside the edges
+        let dx = (margin - left).max(0) + (width - margin - right).min(0);
+        let dy = (top - label_top).max(0) + (height - margin - bottom).min(0);
+        (dx, dy)
+    };
+    let mut max_radius = center.0.min(center.1 - top as f32) - margin as f32;
+    while max_radius > template.px(RADAR_MIN_RADIUS) as f32
+        && (0..data.labels.len()).any(|index| label_shift(index, max_radius) != (0, 0))
+    {
+        max_radius -= 1.0;
     }
 
-    let mut start_angle = 0.0;
+    // Rings at evenly spaced values, then a spoke out to each label
+    for ring in 1..=RADAR_RINGS {
+        let radius = max_radius * ring as f32 / RADAR_RINGS as f32;
+        match template.radar_grid {
+            RadarGrid::Circle => {
+                root.draw(&Circle::new((center.0 as i32, center.1 as i32), radius as i32, BLACK.mix(0.2)))?;
+            }
+            RadarGrid::Polygon => {
+                let mut ring_points: Vec<(i32, i32)> =
+                    (0..data.labels.len()).map(|index| point_at(index, radius)).collect();
+                ring_points.push(ring_points[0]);
+                root.draw(&PathElement::new(ring_points, BLACK.mix(0.2)))?;
+            }
+        }
+    }
+    for (index, label) in data.labels.iter().enumerate() {
+        root.draw(&PathElement::new(
+            vec![(center.0 as i32, center.1 as i32), point_at(index, max_radius)],
+            BLACK.mix(0.3),
+        ))?;
+        let (anchor, _) = radar_label_placement(
+            offset(index, max_radius + label_gap),
+            step_angle * index as f32,
+            label_sizes[index],
+        );
+        let (dx, dy) = label_shift(index, max_radius);
+        let (x, y) = point_at(index, max_radius + label_gap);
+        root.draw(&Text::new(label.to_string(), (x + dx, y + dy), label_style.pos(anchor)))?;
+    }
 
-    for pie_data in &data {
-        let value = pie_data.value;
-        let end_angle = start_angle + value / 100.0 * 2.0 * std::f64::consts::PI;
+    // Ring values: once along the first spoke when every label shares a scale, otherwise on every spoke
+    let ring_color = BLACK.mix(0.6);
+    let ring_style = TextStyle::from(("sans-serif", template.scaled(12.0)).into_font()).color(&ring_color);
+    let shared_scale = ranges.iter().all(|range| *range == ranges[0]);
+    let (labelled, first_ring) = if shared_scale { (&ranges[..1], 0) } else { (&ranges[..], 1) };
+    for (index, range) in labelled.iter().enumerate() {
+        for ring in first_ring..=RADAR_RINGS {
+            let value = range.start + (range.end - range.start) * ring as f64 / RADAR_RINGS as f64;
+            let (x, y) = point_at(index, max_radius * ring as f32 / RADAR_RINGS as f32);
+            let position = (x + template.px(3), y - template.px(14));
+            root.draw(&Text::new(format_plain_number(value), position, ring_style.clone()))?;
+        }
+    }
 
-        let points: Vec<_> = std::iter::once(center)
-            .chain((0..=100).map(|p| {
-                let angle = start_angle + (end_angle - start_angle) * p as f64 / 100.0;
-                (
-                    (center.0 as f64 + radius * angle.cos()).round() as i32,
-                    (center.1 as f64 + radius * angle.sin()).round() as i32,
-                )
-            }))
+    let names: Vec<&str> = data.series.iter().map(|s| s.name.as_str()).collect();
+    let colors = template.colors_for(&names, &[RED, BLUE, GREEN, MAGENTA, CYAN, BLACK]);
+    for (series_index, series) in data.series.iter().enumerate() {
+        let color = colors[series_index % colors.len()];
+        let mut radar_points: Vec<(i32, i32)> = series
+            .values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let range = &ranges[index];
+                // Values outside a user-defined range stay on the center or the outer ring
+                let fraction = ((value - range.start) / (range.end - range.start)).clamp(0.0, 1.0);
+                point_at(index, fraction as f32 * max_radius)
+            })
             .collect();
+        radar_points.push(radar_points[0]);
 
-        let polygon = Polygon::new(points, pie_data.color.filled());
-        root.draw(&polygon)?;
+        // Translucent fills keep the polygons underneath visible
+        root.draw(&Polygon::new(radar_points.clone(), color.mix(0.25).filled()))?;
+        root.draw(&PathElement::new(radar_points.clone(), color.stroke_width(template.px(2) as u32)))?;
+        for point in &radar_points[1..] {
+            root.draw(&Circle::new(*point, template.px(4), color.filled()))?;
+        }
+    }
 
-        // Calculate label position
-        let label_angle = (start_angle + end_angle) / 2.0;
-        let label_distance = radius + 20.0;
-        let label_x = (center.0 as f64 + label_distance * label_angle.cos()).round() as i32;
-        let label_y = (center.1 as f64 + label_distance * label_angle.sin()).round() as i32;
+    draw_radar_legend(root, &data.series, &colors, top, template)?;
 
-        // Draw the label
-        let label_style = TextStyle::from(("sans-serif", 15).into_font()).color(&BLACK);
-        root.draw_text(&pie_data.label, &label_style, (label_x, label_y))?;
+    Ok(())
+}
 
-        start_angle = end_angle;
+// Series names next to their colors in the top right corner
+fn draw_radar_legend<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    series: &[RadarSeries],
+    colors: &[RGBColor],
+    top: i32,
+    template: &Template,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let font = TextStyle::from(("sans-serif", template.scaled(15.0)).into_font()).pos(Pos::new(HPos::Left, VPos::Center));
+    let text_width = series
+        .iter()
+        .map(|s| root.estimate_text_size(&s.name, &font).map(|(width, _)| width))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .max()
+        .unwrap_or(0) as i32;
+    let (row_height, swatch) = (template.px(20), template.px(5));
+    let width = text_width + template.px(35);
+    let height = series.len() as i32 * row_height + template.px(10);
+    let left = root.dim_in_pixel().0 as i32 - width - template.px(RADAR_MARGIN);
+
+    root.draw(&Rectangle::new([(left, top), (left + width, top + height)], WHITE.mix(0.8).filled()))?;
+    root.draw(&Rectangle::new([(left, top), (left + width, top + height)], BLACK))?;
+    for (index, s) in series.iter().enumerate() {
+        let color = colors[index % colors.len()];
+        let y = top + template.px(15) + index as i32 * row_height;
+        let swatch_left = left + template.px(8);
+        root.draw(&Rectangle::new([(swatch_left, y - swatch), (swatch_left + 2 * swatch, y + swatch)], color.filled()))?;
+        root.draw(&Text::new(s.name.clone(), (left + template.px(25), y), font.clone()))?;
     }
 
-    // Draw the title
-    let title_style = TextStyle::from(("sans-serif", 24).into_font()).color(&BLACK);
-    root.draw_text(chart_title, &title_style, (175, 25))?;  // Adjust position as needed
-
     Ok(())
 }
 
+// One set of values to bin, from a value column or a group
+struct Distribution {
+    name: String,
+    values: Vec<f64>,
+}
+
+// More bins than this means the bin width is too small for the data
+const HISTOGRAM_MAX_BINS: usize = 10_000;
+// Points sampled along each density curve
+const DENSITY_SAMPLES: usize = 200;
+
+#[derive(Default)]
+struct HistogramChart;
+
+impl Chart for HistogramChart {
+    type Data = Vec<Distribution>;
+
+    // One distribution per column in `columns` (by default the first column), or one per
+    // value of the group column; empty cells are left out, other non-numeric cells are reported
+    fn load(&self, template: &Template) -> Result<Self::Data, Box<dyn Error>> {
+        let input = &template.inputs[0];
+        let rows = data_loading::load_csv(input)?;
+        let headers = &rows
+            .first()
+            .ok_or_else(|| ChartError::EmptyData {
+                path: input.clone(),
+                reason: None,
+            })?
+            .headers;
+
+        let columns: Vec<String> = if template.columns.is_empty() {
+            headers.iter().take(1).cloned().collect()
+        } else {
+            template
+                .columns
+                .iter()
+                .map(|name| data_loading::find_column(headers, name, input).map(str::to_string))
+                .collect::<Result<_, _>>()?
+        };
+        let group = match &template.group_column {
+            Some(name) => Some(data_loading::find_column(headers, name, input)?),
+            None => None,
+        };
+        if let (Some(group), true) = (group, columns.len() > 1) {
+            let message = format!("a histogram with a group column takes a single value column, found {}", columns.len());
+            return Err(ChartError::invalid_column(input, group, message).into());
+        }
+
+        let mut distributions: Vec<Distribution> = match group {
+            Some(_) => Vec::new(),
+            None => columns
+                .iter()
+                .map(|name| Distribution {
+                    name: name.clone(),
+                    values: Vec::new(),
+                })
+                .collect(),
+        };
+        let mut skipped = Vec::new();
+        for (index, row) in rows.iter().enumerate() {
+            let cell = |column: &str| row.values.get(column).map_or("", |text| text.trim());
+            for (position, column) in columns.iter().enumerate() {
+                let value = match cell(column).parse::<f64>() {
+                    Ok(value) if value.is_finite() => value,
+                    _ if cell(column).is_empty() => continue,
+                    _ => {
+                        if skipped.last() != Some(&(index + 1)) {
+                            skipped.push(index + 1);
+                        }
+                        continue;
+                    }
+                };
+                let target = match group {
+                    None => position,
+                    Some(group) => {
+                        let name = cell(group);
+                        match distributions.iter().position(|d| d.name == name) {
+                            Some(target) => target,
+                            None => {
+                                distributions.push(Distribution {
+                                    name: name.to_string(),
+                                    values: Vec::new(),
+                                });
+                                distributions.len() - 1
+                            }
+                        }
+                    }
+                };
+                distributions[target].values.push(value);
+            }
+        }
 
-use csv;
-use plotters::backend::BitMapBackend;
-use plotters::drawing::IntoDrawingArea;
-use plotters::prelude::{AreaSeries, ChartBuilder, LabelAreaPosition, RED, WHITE};
-use plotters::style::Color;
+        if !skipped.is_empty() {
+            eprintln!(
+                "Warning: skipped {} row(s) of '{}' whose {} is not a number: rows {}",
+                skipped.len(),
+                input,
+                columns.iter().map(|name| format!("'{}'", name)).collect::<Vec<_>>().join(" or "),
+                skipped.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+            );
+        }
+        Ok(distributions)
+    }
 
+    fn validate(&self, data: &Self::Data, template: &Template) -> Result<(), Box<dyn Error>> {
+        let input = &template.inputs[0];
+        if data.iter().all(|distribution| distribution.values.is_empty()) {
+            return Err(ChartError::EmptyData {
+                path: input.clone(),
+                reason: Some("no numeric values to bin".to_string()),
+            }
+            .into());
+        }
+        // Only value columns can be empty; a group value always comes with a number
+        if let Some(empty) = data.iter().find(|distribution| distribution.values.is_empty()) {
+            return Err(ChartError::invalid_column(input, &empty.name, "has no numeric values to bin").into());
+        }
+        let bins = histogram_bins(data, template);
+        if bins.count > HISTOGRAM_MAX_BINS {
+            let message = format!("a bin width of {} gives {} bins; use wider bins or fewer of them", bins.width, bins.count);
+            return Err(ChartError::invalid(input, message).into());
+        }
+        Ok(())
+    }
 
+    fn render(&self, data: &Self::Data, template: &Template) -> Result<(), Box<dyn Error>> {
+        let bins = histogram_bins(data, template);
+        with_backend!(template, HISTOGRAM_SIZE, |root| draw_histogram(&root, data, bins, template))
+    }
+}
 
+// Bins over the values of every distribution, so that they line up
+fn histogram_bins(distributions: &[Distribution], template: &Template) -> histogram::Bins {
+    let all_values: Vec<f64> = distributions.iter().flat_map(|d| d.values.iter().copied()).collect();
+    histogram::bins(&all_values, template.binning)
+}
 
+fn draw_histogram<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    distributions: &[Distribution],
+    bins: histogram::Bins,
+    template: &Template,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    root.fill(&WHITE)?;
 
-fn validate_csv_data_for_line_area(filename: &str) -> Result<(), Box<dyn Error>> {
-    let file = File::open(filename)?;
-    let mut rdr = Reader::from_reader(file);
+    // Density curves are scaled to counts, so they follow the tops of the bars
+    let counts: Vec<Vec<usize>> = distributions.iter().map(|d| bins.counts(&d.values)).collect();
+    let curves: Vec<Vec<(f64, f64)>> = distributions
+        .iter()
+        .filter(|_| template.density)
+        .map(|d| {
+            let scale = d.values.len() as f64 * bins.width;
+            histogram::density_curve(&d.values, bins.start, bins.end(), DENSITY_SAMPLES, scale)
+        })
+        .collect();
+
+    let heights = counts.iter().flatten().map(|count| *count as f64);
+    let curve_heights = curves.iter().flatten().map(|(_, y)| *y);
+    let top = axis::data_extent(heights.chain(curve_heights)).map_or(0.0, |(_, max)| max);
+    let x_range = axis::resolve(&template.x_axis, (bins.start, bins.end()))?;
+    let y_range = axis::resolve(&template.y_axis, (0.0, if top > 0.0 { top * 1.05 } else { 1.0 }))?;
+
+    let mut ctx = ChartBuilder::on(root)
+        .caption(&template.title, ("Arial", template.scaled(40.0)).into_font())
+        .margin(template.px(5))
+        .set_label_area_size(LabelAreaPosition::Left, template.px(40))
+        .set_label_area_size(LabelAreaPosition::Bottom, template.px(40))
+        .build_cartesian_2d(x_range, y_range)?;
+
+    // A single distribution is described by its column name, and every histogram counts values
+    let x_desc = match (&template.x_axis.label, distributions) {
+        (Some(label), _) => Some(label.clone()),
+        (None, [distribution]) => Some(distribution.name.clone()),
+        (None, _) => None,
+    };
+    let y_desc = template.y_axis.label.clone().unwrap_or_else(|| "Count".to_string());
+    let mut mesh = ctx.configure_mesh();
+    if let Some(label) = &x_desc {
+        mesh.x_desc(label.as_str());
+    }
+    mesh.y_desc(y_desc.as_str())
+        .label_style(("sans-serif", template.scaled(12.0)))
+        .axis_desc_style(("sans-serif", template.scaled(15.0)))
+        .draw()?;
 
-    for (line, result) in rdr.records().enumerate() {
-        let record = result?;
+    // Overlapping distributions are see-through, each bin outlined in its distribution's color
+    let names: Vec<&str> = distributions.iter().map(|d| d.name.as_str()).collect();
+    let colors = template.colors_for(&names, &[]);
+    let several = distributions.len() > 1;
+    let alpha = if several { 0.45 } else { 0.85 };
+    let (line_width, swatch) = (template.px(2) as u32, template.px(5));
+    for (index, (distribution, counts)) in distributions.iter().zip(&counts).enumerate() {
+        let color = colors[index];
+        let filled_bins = move || {
+            counts.iter().enumerate().filter(|(_, count)| **count > 0).map(move |(bin, count)| {
+                let left = bins.start + bins.width * bin as f64;
+                [(left, 0.0), (left + bins.width, *count as f64)]
+            })
+        };
 
-        // Check if there are exactly 2 columns
-        if record.len() != 2 {
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                format!("Line {}: Expected exactly 2 columns for x and y values.", line + 1),
-            )));
+        let annotation = ctx.draw_series(filled_bins().map(|corners| Rectangle::new(corners, color.mix(alpha).filled())))?;
+        if several {
+            annotation
+                .label(distribution.name.as_str())
+                .legend(move |(x, y)| Rectangle::new([(x, y - swatch), (x + 2 * swatch, y + swatch)], color.mix(alpha).filled()));
         }
-
-        // Validate the x and y columns to ensure they can be parsed as i32
-        if record[0].trim().parse::<i32>().is_err() {
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                format!("Line {}: Invalid x value. Expected an integer.", line + 1),
-            )));
+        ctx.draw_series(filled_bins().map(|corners| Rectangle::new(corners, color.stroke_width(1))))?;
+        if let Some(curve) = curves.get(index) {
+            ctx.draw_series(LineSeries::new(curve.iter().copied(), color.stroke_width(line_width)))?;
         }
+    }
 
-        if record[1].trim().parse::<i32>().is_err() {
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                format!("Line {}: Invalid y value. Expected an integer.", line + 1),
-            )));
-        }
+    if several {
+        ctx.configure_series_labels()
+            .label_font(("sans-serif", template.scaled(15.0)))
+            .legend_area_size(template.px(30))
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
     }
 
     Ok(())
 }
 
-fn read_data_from_csv_line_area(filename: &str) -> Result<Vec<(i32, i32)>, Box<dyn std::error::Error>> {
-    let mut data = Vec::new();
-    let file = File::open(filename)?;
-    let mut rdr = csv::Reader::from_reader(file);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+    use templating::ChartKind;
 
-    for result in rdr.records() {
-        let record = result?;
-        let label: i32 = record[0].parse()?;
-        let value: i32 = record[1].parse()?;
-        data.push((label, value));
+    fn rows(headers: &[&str], cells: &[&[&str]]) -> Vec<data_loading::DataRow> {
+        let headers = Rc::new(headers.iter().map(|header| header.to_string()).collect::<Vec<_>>());
+        cells
+            .iter()
+            .map(|cells| data_loading::DataRow {
+                headers: Rc::clone(&headers),
+                values: headers.iter().cloned().zip(cells.iter().map(|cell| cell.to_string())).collect::<HashMap<_, _>>(),
+            })
+            .collect()
     }
-    Ok(data)
-}
 
-fn draw_line_and_area(
-    input_file: &str,
-    output_file: &str,
-    chart_title: &str
-) -> Result<(), Box<dyn std::error::Error>> {
-    validate_csv_data_for_line_area(input_file)?;
-    let data = read_data_from_csv_line_area(input_file)?;
+    #[test]
+    fn picks_the_bar_columns_by_name_or_position() {
+        let data = rows(&["Team", "Wins", "Losses"], &[&["a", "3", "1"]]);
+        let mut template = Template::new(ChartKind::Bar);
+        template.inputs.push("teams.csv".to_string());
+        let columns = |template: &Template| {
+            let columns = resolve_bar_columns(&data, template).unwrap();
+            (columns.category, columns.values)
+        };
+        assert_eq!(columns(&template), ("Team".to_string(), vec!["Wins".to_string()]));
 
-    // Determine the maximum x and y values for the range
-    let max_x = data.iter().map(|(x, _)| *x).max().unwrap_or(10) + 1;  // +1 to make the plot a bit more spacious
-    let max_y = data.iter().map(|(_, y)| *y).max().unwrap_or(50) + 5;  // +5 for the same reason
+        template.columns = parse_column_list(" team , LOSSES ");
+        assert_eq!(columns(&template), ("Team".to_string(), vec!["Losses".to_string()]));
 
-    let root_area = BitMapBackend::new(output_file, (600, 400)).into_drawing_area();
-    root_area.fill(&WHITE)?;
+        template.columns = vec![String::new(), "Losses".to_string()];
+        assert_eq!(columns(&template), ("Team".to_string(), vec!["Losses".to_string()]));
 
-    let mut ctx = ChartBuilder::on(&root_area)
-    .set_label_area_size(LabelAreaPosition::Left, 40)
-    .set_label_area_size(LabelAreaPosition::Bottom, 40)
-    .caption(chart_title, ("sans-serif", 40))
-    .build_cartesian_2d(0..max_x, 0..max_y)?
-    ;
+        template.columns = vec!["Team".to_string(), "Draws".to_string()];
+        let message = resolve_bar_columns(&data, &template).err().unwrap().to_string();
+        assert!(message.contains("'Draws'") && message.contains("Team, Wins, Losses"), "{}", message);
+    }
 
+    #[test]
+    fn renders_the_same_chart_as_png_or_svg() {
+        let dir = std::env::temp_dir();
+        let input = dir.join(format!("main_render_{}.csv", std::process::id()));
+        std::fs::write(&input, "name,age\nAda,36\nAlan,41\n").unwrap();
+
+        for (extension, magic) in [("png", &b"\x89PNG"[..]), ("svg", &b"<svg"[..])] {
+            let output = dir.join(format!("main_render_{}.{}", std::process::id(), extension));
+            let mut template = Template::new(ChartKind::Bar);
+            template.inputs.push(input.to_string_lossy().to_string());
+            template.output = output.to_string_lossy().to_string();
+            let result = render_template(&template);
+            let written = std::fs::read(&output);
+            let _ = std::fs::remove_file(&output);
+            result.unwrap();
+            assert!(written.unwrap().starts_with(magic), "{}", extension);
+        }
+        std::fs::remove_file(&input).unwrap();
+    }
 
-    ctx.configure_mesh().draw()?;
+    // Writes `contents` to a scratch CSV file named after the test
+    fn scratch_csv(name: &str, contents: &str) -> String {
+        let path = std::env::temp_dir().join(format!("main_{}_{}.csv", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path.to_string_lossy().to_string()
+    }
 
-    ctx.draw_series(
-        AreaSeries::new(data.iter().map(|(x, y)| (*x, *y)), 0, &RED.mix(0.2)).border_style(&RED),
-    )
-    .unwrap();
+    // Loads `contents` as the template's only input and checks it, as `charts::run` does
+    fn load_and_validate<C: Chart>(mut template: Template, name: &str, contents: &str) -> Result<C::Data, Box<dyn Error>> {
+        template.inputs = vec![scratch_csv(name, contents)];
+        let chart = C::default();
+        let data = chart.load(&template);
+        std::fs::remove_file(&template.inputs[0]).unwrap();
+        let data = data?;
+        chart.validate(&data, &template)?;
+        Ok(data)
+    }
 
-    Ok(())
-}
+    #[test]
+    fn reads_fractional_negative_and_scientific_values() {
+        let path = scratch_csv("floats", "x,y\n1.25,-2\n-0.5,1e3\n2,2.5E-1\n");
+        let scatter = validate_csv_data_for_scatter(&path).and_then(|_| read_data_from_csv_scatter(&path));
+        let line = validate_csv_data_for_line_area(&path, None).and_then(|_| read_data_from_csv_line_area(&path, &[], None));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(scatter.unwrap().0, [(1.25, -2.0), (-0.5, 1000.0), (2.0, 0.25)]);
+        let line = line.unwrap();
+        assert_eq!(line.xs, [-0.5, 1.25, 2.0]);
+        assert_eq!(line.series[0].values, [1000.0, -2.0, 0.25]);
+
+        let path = scratch_csv("not_a_number", "x,y\n1,2\n3,four\n");
+        let error = validate_csv_data_for_scatter(&path).unwrap_err().to_string();
+        std::fs::remove_file(&path).unwrap();
+        assert!(error.contains("row 2, column 'y'"), "{}", error);
+    }
 
+    #[test]
+    fn splits_scatter_points_by_group_in_file_order() {
+        let path = scratch_csv("scatter_group", "X,Y,Kind\n1,2,b\n3,4.5,a\n5,6,b\n");
+        let grouped = read_grouped_scatter_series(&path, "kind", &[]);
+        let picked = read_grouped_scatter_series(&path, "Kind", &["Y".to_string(), "X".to_string()]);
+        let by_file = read_scatter_series_from_files(std::slice::from_ref(&path));
+        std::fs::remove_file(&path).unwrap();
+
+        let series = grouped.unwrap();
+        let summary: Vec<(&str, &[DataPoint])> = series.iter().map(|s| (s.name.as_str(), s.points.as_slice())).collect();
+        assert_eq!(summary, [("b", &[(1.0, 2.0), (5.0, 6.0)][..]), ("a", &[(3.0, 4.5)][..])]);
+        assert_eq!(picked.unwrap()[1].points, [(4.5, 3.0)]);
+        // Three columns are one too many for a file that is a series of its own
+        assert!(by_file.is_err());
+
+        let path = scratch_csv("scatter_series", "x,y\n1,2\n");
+        let series = read_scatter_series_from_files(std::slice::from_ref(&path));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(series.unwrap()[0].name, format!("main_scatter_series_{}", std::process::id()));
+    }
 
+    #[test]
+    fn reads_line_chart_dates_as_timestamps() {
+        let day = datetime::days_from_civil(2023, 10, 29) * 86_400;
+        let path = scratch_csv("dates", "when,sales\n2023-10-29,5\n2023-10-30 12:00,7\n");
+        let iso = read_data_from_csv_line_area(&path, &[], None).unwrap();
+        let wrong_format = validate_csv_data_for_line_area(&path, Some("%d/%m/%Y"));
+        std::fs::remove_file(&path).unwrap();
+        assert!(iso.is_time);
+        assert_eq!(iso.xs, [day as f64, (day + 86_400 + 43_200) as f64]);
+        assert_eq!(iso.series[0].values, [5.0, 7.0]);
+        assert!(wrong_format.unwrap_err().to_string().contains("row 1, column 'when'"));
+
+        let path = scratch_csv("date_format", "when,sales\n29/10/2023,5\n");
+        let formatted = read_data_from_csv_line_area(&path, &[], Some("%d/%m/%Y")).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!((formatted.xs, formatted.is_time), (vec![day as f64], true));
+    }
 
+    #[test]
+    fn stacks_line_series_on_the_ones_before_them() {
+        let path = scratch_csv("line_stacked", "Date,a,b,c\n03/01/2024,1,10,0\n01/01/2024,2,20,0\n02/01/2024,3.5,30,0\n");
+        let columns = ["Date".to_string(), "a".to_string(), "b".to_string()];
+        let data = read_data_from_csv_line_area(&path, &columns, Some("%d/%m/%Y"));
+        let too_few = read_data_from_csv_line_area(&path, &columns[..1], None);
+        std::fs::remove_file(&path).unwrap();
+
+        let data = data.unwrap();
+        let day = |d: i64| (datetime::days_from_civil(2024, 1, d as u32) * 86_400) as f64;
+        assert!(data.is_time);
+        assert_eq!(data.xs, [day(1), day(2), day(3)]);
+        assert_eq!(line_tops(&data, LineMode::Area), [vec![2.0, 3.5, 1.0], vec![20.0, 30.0, 10.0]]);
+        assert_eq!(line_tops(&data, LineMode::Stacked), [vec![2.0, 3.5, 1.0], vec![22.0, 33.5, 11.0]]);
+        assert!(too_few.is_err());
+    }
 
-fn read_from_csv_radar(file_path: &str) -> Result<Vec<(String, f32)>, Box<dyn Error>> {
-    let mut rdr = ReaderBuilder::new().from_path(file_path)?;
-    let mut data = Vec::new();
+    #[test]
+    fn reads_one_radar_series_per_column_or_group() {
+        let path = scratch_csv("radar_columns", "Label,a,b\nx,1,4\ny,2,5\nz,3,6\n");
+        let data = read_from_csv_radar(&path, None, &[]);
+        std::fs::remove_file(&path).unwrap();
+        let data = data.unwrap();
+        assert_eq!(data.labels, ["x", "y", "z"]);
+        let series: Vec<(&str, &[f64])> = data.series.iter().map(|s| (s.name.as_str(), s.values.as_slice())).collect();
+        assert_eq!(series, [("a", &[1.0, 2.0, 3.0][..]), ("b", &[4.0, 5.0, 6.0][..])]);
+
+        let path = scratch_csv("radar_group", "Team,Label,Score\nred,x,1\nblue,x,4\nred,y,2\nblue,y,5\nred,z,3\nblue,z,6\n");
+        let data = read_from_csv_radar(&path, Some("Team"), &[]);
+        std::fs::remove_file(&path).unwrap();
+        let data = data.unwrap();
+        assert_eq!(data.labels, ["x", "y", "z"]);
+        let series: Vec<(&str, &[f64])> = data.series.iter().map(|s| (s.name.as_str(), s.values.as_slice())).collect();
+        assert_eq!(series, [("red", &[1.0, 2.0, 3.0][..]), ("blue", &[4.0, 5.0, 6.0][..])]);
+
+        let path = scratch_csv("radar_gaps", "Team,Label,Score\nred,x,1\nred,x,2\nblue,y,3\n");
+        let error = read_from_csv_radar(&path, Some("Team"), &[]).err().unwrap().to_string();
+        std::fs::remove_file(&path).unwrap();
+        assert!(error.contains("row 2: series 'red' already has a value for 'x'"), "{}", error);
+    }
 
-    for result in rdr.records() {
-        let record = result?;
-        let label = record[0].to_string();
-        let value: f32 = record[1].parse()?;
-        data.push((label, value));
+    #[test]
+    fn scales_radar_labels_together_or_one_by_one() {
+        let data = RadarData {
+            labels: vec!["x".to_string(), "y".to_string(), "z".to_string()],
+            series: vec![
+                RadarSeries {
+                    name: "a".to_string(),
+                    values: vec![1.0, 2.0, 3.0],
+                },
+                RadarSeries {
+                    name: "b".to_string(),
+                    values: vec![4.0, 5.0, 6.0],
+                },
+            ],
+        };
+        let mut template = Template::new(ChartKind::Radar);
+        assert_eq!(radar_ranges(&data, &template).unwrap(), [0.0..8.0, 0.0..8.0, 0.0..8.0]);
+
+        template.radar_scale = RadarScale::PerAxis;
+        template.radar_limits.push(templating::RadarAxisLimit {
+            label: "z".to_string(),
+            min: -1.0,
+            max: 10.0,
+        });
+        assert_eq!(radar_ranges(&data, &template).unwrap(), [0.0..4.0, 0.0..8.0, -1.0..10.0]);
+
+        template.y_axis.max = Some(20.0);
+        assert_eq!(radar_ranges(&data, &template).unwrap()[0], 0.0..20.0);
     }
 
-    Ok(data)
-}
+    #[test]
+    fn places_radar_labels_outside_the_chart() {
+        let place = |angle: f32| radar_label_placement((100.0, 50.0), angle, (30, 10));
 
+        let (pos, rect) = place(0.0);
+        assert!(matches!((pos.h_pos, pos.v_pos), (HPos::Left, VPos::Center)));
+        assert_eq!(rect, [(100, 45), (130, 55)]);
 
-use plotters::style::IntoFont;
-use plotters::element::Circle;
-use plotters::prelude::*;
+        let (pos, rect) = place(std::f32::consts::FRAC_PI_2);
+        assert!(matches!((pos.h_pos, pos.v_pos), (HPos::Center, VPos::Bottom)));
+        assert_eq!(rect, [(85, 40), (115, 50)]);
+
+        let (pos, rect) = place(1.25 * std::f32::consts::PI);
+        assert!(matches!((pos.h_pos, pos.v_pos), (HPos::Right, VPos::Top)));
+        assert_eq!(rect, [(70, 50), (100, 60)]);
+    }
+
+    #[test]
+    fn rejects_negative_pie_slices_by_row_and_column() {
+        let pie = || Template::new(ChartKind::Pie);
+        let error = load_and_validate::<PieChart>(pie(), "pie_negative", "Category,Share\na,3\nb,-1\n").err().unwrap();
+        assert!(
+            matches!(error.downcast_ref(), Some(ChartError::Conversion { row: 2, column, .. }) if column == "Share"),
+            "{}",
+            error
+        );
+        assert!(error.to_string().contains("row 2, column 'Share': expected a number of 0 or more, found '-1'"), "{}", error);
+
+        let error = load_and_validate::<PieChart>(pie(), "pie_zero", "Category,Share\na,0\nb,0\n").err().unwrap();
+        assert!(matches!(error.downcast_ref(), Some(ChartError::EmptyData { .. })), "{}", error);
+        assert!(error.to_string().contains("must add up to more than zero"), "{}", error);
+
+        let data = load_and_validate::<PieChart>(pie(), "pie_valid", "Category,Share\na,0\nb,2.5\n").unwrap();
+        assert_eq!(data.iter().map(|d| d.value).collect::<Vec<_>>(), [0.0, 2.5]);
+    }
 
+    #[test]
+    fn annotates_pie_slices_and_keeps_their_labels_apart() {
+        let datum = PieChartData::new("a".to_string(), 1500.0, WHITE.to_rgba());
+        assert_eq!(pie_annotation(&datum, 6000.0, PieAnnotation::Percent).as_deref(), Some("25.0%"));
+        assert_eq!(pie_annotation(&datum, 6000.0, PieAnnotation::Both).as_deref(), Some("1500 (25.0%)"));
+        assert_eq!(pie_annotation(&datum, 6000.0, PieAnnotation::None), None);
+        assert_eq!(contrasting_text_color(&RGBAColor(255, 255, 0, 1.0)), BLACK);
+        assert_eq!(contrasting_text_color(&RGBAColor(0, 0, 128, 1.0)), WHITE);
+        // A faint navy is mostly the white canvas behind it
+        assert_eq!(contrasting_text_color(&RGBAColor(0, 0, 128, 0.2)), BLACK);
+
+        let mut labels: Vec<PieLabel> = [40, 45, 95]
+            .iter()
+            .enumerate()
+            .map(|(index, y)| PieLabel {
+                index,
+                text: String::new(),
+                rim: (0, 0),
+                ideal: (0, *y),
+                y: 0,
+                right_side: true,
+                leader: false,
+            })
+            .collect();
+        spread_pie_labels(&mut labels.iter_mut().collect::<Vec<_>>(), 20, 0, 80);
+        assert_eq!(labels.iter().map(|label| label.y).collect::<Vec<_>>(), [40, 60, 80]);
+    }
 
+    #[test]
+    fn explodes_pie_slices_from_the_column_and_the_options() {
+        let mut template = Template::new(ChartKind::Pie);
+        template.explode = vec!["b".to_string()];
+        let contents = "Category,explode,Share\na,yes,1\nb,,2\nc,0.3,3\nd,no,4\n";
+        let data = load_and_validate::<PieChart>(template, "pie_explode", contents).unwrap();
+        assert_eq!(data.iter().map(|d| d.value).collect::<Vec<_>>(), [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(data.iter().map(|d| d.explode).collect::<Vec<_>>(), [PIE_EXPLODE, PIE_EXPLODE, 0.3, 0.0]);
+
+        let mut template = Template::new(ChartKind::Pie);
+        template.explode = vec!["z".to_string()];
+        let missing = load_and_validate::<PieChart>(template, "pie_explode_missing", "Category,Share\na,1\n");
+        assert!(missing.err().unwrap().to_string().contains("Cannot explode 'z'"));
+
+        let path = scratch_csv("pie_explode_bad", "Category,Share,explode\na,1,2\n");
+        let error = read_data_from_csv_pie(&path, &Template::new(ChartKind::Pie)).err().unwrap().to_string();
+        std::fs::remove_file(&path).unwrap();
+        assert!(error.contains("row 1, column 'explode'"), "{}", error);
+    }
 
+    #[test]
+    fn reads_point_and_slice_colors_from_a_color_column() {
+        let path = scratch_csv("scatter_colors", "x,Color,y\n1,#ff000080,2\n3,steelblue,4\n");
+        let scatter = validate_csv_data_for_scatter(&path).and_then(|_| read_data_from_csv_scatter(&path));
+        let pie = read_data_from_csv_pie(&path, &Template::new(ChartKind::Pie));
+        std::fs::remove_file(&path).unwrap();
+        let (points, colors) = scatter.unwrap();
+        assert_eq!(points, [(1.0, 2.0), (3.0, 4.0)]);
+        assert_eq!(colors, [RGBAColor(255, 0, 0, 128.0 / 255.0), RGBAColor(70, 130, 180, 1.0)]);
+        let pie = pie.unwrap();
+        assert_eq!((pie[1].label.as_str(), pie[1].value), ("3", 4.0));
+        assert_eq!(pie[1].color, RGBAColor(70, 130, 180, 1.0));
+
+        let path = scratch_csv("bad_color", "x,y,color\n1,2,red\n3,4,reddish\n");
+        let error = validate_csv_data_for_scatter(&path).unwrap_err().to_string();
+        std::fs::remove_file(&path).unwrap();
+        assert!(error.contains("row 2, column 'color': expected a color as #RRGGBB"), "{}", error);
+    }
 
-fn draw_radar_chart(data: &[(String, f32)], output_file: &str) -> Result<(), Box<dyn Error>> {
-    // Prepare drawing area
-    let root = BitMapBackend::new(output_file, (800, 800)).into_drawing_area();
-    root.fill(&WHITE)?;
+    #[test]
+    fn stacks_bars_by_sign_and_scales_percent_bars_to_a_hundred() {
+        let series = |name: &str, values: Vec<f64>| BarSeries { name: name.to_string(), values, colors: Vec::new() };
+        let data = BarChartData {
+            categories: vec!["x".to_string(), "y".to_string()],
+            series: vec![series("a", vec![2.0, -1.0]), series("b", vec![3.0, f64::NAN]), series("c", vec![-5.0, 4.0])],
+        };
 
-    let max_val = data.iter().map(|(_, v)| *v).fold(0.0, f32::max);
+        let grouped = bar_segments(&data, BarMode::Grouped);
+        assert_eq!(grouped[0], [(0.0, 2.0), (0.0, -1.0)]);
+        assert_eq!(grouped[2], [(0.0, -5.0), (0.0, 4.0)]);
 
-    // Create chart
-    let mut chart = ChartBuilder::on(&root)
-        .x_label_area_size(40)
-        .y_label_area_size(40)
-        .build_cartesian_2d(-350..350, -350..350)?;
+        let stacked = bar_segments(&data, BarMode::Stacked);
+        assert_eq!(stacked[0], [(0.0, 2.0), (0.0, -1.0)]);
+        assert_eq!(stacked[1][0], (2.0, 5.0));
+        assert!(stacked[1][1].0.is_nan() && stacked[1][1].1.is_nan());
+        assert_eq!(stacked[2], [(0.0, -5.0), (0.0, 4.0)]);
 
-// Assuming you've already set up your drawing area, backend, etc.
+        let percent = bar_segments(&data, BarMode::Percent);
+        assert_eq!(percent[0], [(0.0, 20.0), (0.0, -20.0)]);
+        assert_eq!(percent[1][0], (20.0, 50.0));
+        assert_eq!(percent[2], [(0.0, -50.0), (0.0, 80.0)]);
+    }
 
+    #[test]
+    fn sorts_bar_categories_and_folds_the_rest_into_other() {
+        let red = RED.to_rgba();
+        let bars = || BarChartData {
+            categories: ["a", "b", "c", "d"].iter().map(|name| name.to_string()).collect(),
+            series: vec![
+                BarSeries { name: "x".to_string(), values: vec![1.0, 5.0, 3.0, f64::NAN], colors: vec![red; 4] },
+                BarSeries { name: "y".to_string(), values: vec![1.0, 1.0, 4.0, 2.0], colors: vec![red; 4] },
+            ],
+        };
 
+        let mut template = Template::new(ChartKind::Bar);
+        template.sort = Some(SortOrder::Ascending);
+        let mut data = bars();
+        arrange_bar_categories(&mut data, &template);
+        // "a" and "d" tie, so they stay in file order
+        assert_eq!(data.categories, ["a", "d", "b", "c"]);
+        assert_eq!(data.series[1].values, [1.0, 2.0, 1.0, 4.0]);
+
+        let mut template = Template::new(ChartKind::Bar);
+        template.top = Some(2);
+        let mut data = bars();
+        arrange_bar_categories(&mut data, &template);
+        assert_eq!(data.categories, ["c", "b", "Other"]);
+        assert_eq!(data.series[0].values, [3.0, 5.0, 1.0]);
+        assert_eq!(data.series[1].values, [4.0, 1.0, 3.0]);
+        assert_eq!(data.series[0].colors, [red; 3]);
+    }
 
-    let center = (400.0, 400.0);  // Center coordinates
-    let max_radius = 350.0;      // Maximum radius value
+    #[test]
+    fn reports_column_mistakes_as_data_errors_before_drawing() {
+        let mut template = Template::new(ChartKind::Bar);
+        template.columns = vec!["Category".to_string(), "a".to_string(), "b".to_string()];
+        template.group_column = Some("Team".to_string());
+        let error = load_and_validate::<BarChart>(template, "bar_group", "Category,a,b,Team\nx,1,2,t\n").err().unwrap();
+        assert!(
+            matches!(error.downcast_ref(), Some(ChartError::InvalidData { column: Some(column), .. }) if column == "Team"),
+            "{}",
+            error
+        );
+
+        let mut template = Template::new(ChartKind::Scatter);
+        template.inputs = vec!["a.csv".to_string(), "b.csv".to_string()];
+        template.group_column = Some("Team".to_string());
+        let error = ScatterChart.load(&template).err().unwrap();
+        assert!(
+            matches!(error.downcast_ref(), Some(ChartError::InvalidData { column: Some(column), .. }) if column == "Team"),
+            "{}",
+            error
+        );
+
+        let mut template = Template::new(ChartKind::Radar);
+        template.inputs.push("radar.csv".to_string());
+        template.radar_limits.push(templating::RadarAxisLimit {
+            label: "Range".to_string(),
+            min: 0.0,
+            max: 1.0,
+        });
+        let data = RadarData {
+            labels: vec!["Speed".to_string(), "Power".to_string(), "Grip".to_string()],
+            series: Vec::new(),
+        };
+        let error = RadarChart.validate(&data, &template).unwrap_err();
+        assert!(matches!(error.downcast_ref(), Some(ChartError::InvalidData { .. })), "{}", error);
+    }
 
-    // Ensure the backend and drawing root is set up before this
-    // For example: 
-    // let root = drawing_backend.into_drawing_area();
+    #[test]
+    fn pads_bar_values_away_from_the_zero_baseline() {
+        let bars = |values: &[f64]| vec![values.iter().map(|value| (0.0, *value)).collect::<Vec<_>>()];
+        let mut template = Template::new(ChartKind::Bar);
+        assert_eq!(bar_value_range(&bars(&[5.0, 20.0]), &template).unwrap(), 0.0..21.0);
+        assert_eq!(bar_value_range(&bars(&[-10.0, 10.0]), &template).unwrap(), -11.0..11.0);
+        assert_eq!(bar_value_range(&bars(&[-20.0, -10.0]), &template).unwrap(), -21.0..0.0);
+        assert_eq!(bar_value_range(&bars(&[0.0, 0.0]), &template).unwrap(), 0.0..1.0);
+
+        template.y_axis.max = Some(50.0);
+        assert_eq!(bar_value_range(&bars(&[5.0, 20.0]), &template).unwrap(), 0.0..50.0);
+    }
 
-    for i in 0..5 {
-        let radius_percentages = [0.0, 0.25, 0.5, 0.75, 1.0];
-        let label_values = [0, 25, 50, 75, 100];
+    #[test]
+    fn reads_float_and_negative_bars_and_skips_rows_without_a_number() {
+        let template = Template::new(ChartKind::Bar);
+        let data = load_and_validate::<BarChart>(template, "bar_floats", "Category,Value\na,1.5\nb,-2\nc,n/a\n").unwrap();
+        assert_eq!(data.categories, ["a", "b"]);
+        assert_eq!(data.series[0].values, [1.5, -2.0]);
 
-        let radius_percentage = radius_percentages[i];
-        let label_value = label_values[i];
-        
-        let radius = max_radius * radius_percentage;
+        let template = Template::new(ChartKind::Bar);
+        let error = load_and_validate::<BarChart>(template, "bar_no_numbers", "Category,Value\na,x\n").err().unwrap();
+        assert!(matches!(error.downcast_ref(), Some(ChartError::EmptyData { .. })), "{}", error);
+    }
 
-        // Drawing the circle
-        root.draw(&Circle::new((center.0 as i32, center.1 as i32), radius as i32,  &BLACK.mix(0.1)))?;
+    #[test]
+    fn reads_one_bar_series_per_value_column_or_group() {
+        let mut template = Template::new(ChartKind::Bar);
+        template.columns = vec!["Year".to_string(), "North".to_string(), "South".to_string()];
+        let data = load_and_validate::<BarChart>(template, "bar_wide", "Year,North,South\n2023,1,2\n2024,3,4\n").unwrap();
+        assert_eq!(data.categories, ["2023", "2024"]);
+        assert_eq!(data.series.iter().map(|series| series.name.as_str()).collect::<Vec<_>>(), ["North", "South"]);
+        assert_eq!(data.series[1].values, [2.0, 4.0]);
+
+        let mut template = Template::new(ChartKind::Bar);
+        template.group_column = Some("Region".to_string());
+        let csv = "Year,Region,Sales\n2023,North,1\n2023,South,2\n2024,North,3\n";
+        let data = load_and_validate::<BarChart>(template, "bar_long", csv).unwrap();
+        assert_eq!(data.categories, ["2023", "2024"]);
+        assert_eq!(data.series.iter().map(|series| series.name.as_str()).collect::<Vec<_>>(), ["North", "South"]);
+        assert_eq!(data.series[0].values, [1.0, 3.0]);
+        assert!(data.series[1].values[0] == 2.0 && data.series[1].values[1].is_nan());
+    }
 
-        // Calculate label position
-        let label_x = center.0 + radius;  // Positioned to the right of the circle
-        let label_y = center.1;
+    #[test]
+    fn rejects_histogram_columns_without_numbers() {
+        let mut template = Template::new(ChartKind::Histogram);
+        template.columns = vec!["a".to_string(), "b".to_string()];
+        template.density = true;
+        let error = load_and_validate::<HistogramChart>(template, "hist_empty", "a,b\n1,\n2,\n3,\n").err().unwrap();
+        assert!(
+            matches!(error.downcast_ref(), Some(ChartError::InvalidData { column: Some(column), .. }) if column == "b"),
+            "{}",
+            error
+        );
+
+        let mut template = Template::new(ChartKind::Histogram);
+        template.binning = templating::Binning::Width(1e-6);
+        let error = load_and_validate::<HistogramChart>(template, "hist_narrow", "a\n0\n1\n").err().unwrap();
+        assert!(matches!(error.downcast_ref(), Some(ChartError::InvalidData { .. })), "{}", error);
+    }
 
-        // Drawing the label
-        root.draw(&Text::new(
-            label_value.to_string(),
-            (label_x as i32, label_y as i32), // Convert to integers for drawing
-            ("Arial", 15).into_font()
-        ))?;
+    #[test]
+    fn reads_one_histogram_distribution_per_column_or_group() {
+        let mut template = Template::new(ChartKind::Histogram);
+        template.columns = vec!["b".to_string(), "a".to_string()];
+        let data = load_and_validate::<HistogramChart>(template, "hist_columns", "a,b\n1,4\n,5\n3,x\n").unwrap();
+        assert_eq!(data.iter().map(|d| d.name.as_str()).collect::<Vec<_>>(), ["b", "a"]);
+        assert_eq!((data[0].values.as_slice(), data[1].values.as_slice()), (&[4.0, 5.0][..], &[1.0, 3.0][..]));
+
+        let mut template = Template::new(ChartKind::Histogram);
+        template.columns = vec!["Height".to_string()];
+        template.group_column = Some("Team".to_string());
+        let csv = "Team,Height\nred,1.5\nblue,1.7\nred,1.6\n";
+        let data = load_and_validate::<HistogramChart>(template, "hist_groups", csv).unwrap();
+        assert_eq!(data.iter().map(|d| d.name.as_str()).collect::<Vec<_>>(), ["red", "blue"]);
+        assert_eq!(data[0].values, [1.5, 1.6]);
     }
 
-    let step_angle = 2.0 * std::f32::consts::PI / data.len() as f32;
-    let mut radar_points = Vec::new();
+    #[test]
+    fn defaults_the_bar_value_to_the_first_column_left_over() {
+        let mut template = Template::new(ChartKind::Bar);
+        template.inputs.push("teams.csv".to_string());
+        template.columns = vec!["Team".to_string()];
+        let value = |data: &[data_loading::DataRow], template: &Template| {
+            resolve_bar_columns(data, template).ok().map(|columns| columns.values)
+        };
 
-    for (index, (label, value)) in data.iter().enumerate() {
-        let scaled_value = (*value / max_val) * 350.0;
-        let x = center.0 as f32 + scaled_value * (step_angle * index as f32).cos();
-        let y = center.1 as f32 - scaled_value * (step_angle * index as f32).sin();
+        // The value may come before the category, and color columns are passed over
+        let data = rows(&["Color", "Wins", "Team"], &[&["red", "3", "a"]]);
+        assert_eq!(value(&data, &template), Some(vec!["Wins".to_string()]));
 
-        let x_label = center.0 as f32 + 375.0 * (step_angle * index as f32).cos();
-        let y_label = center.1 as f32 - 375.0 * (step_angle * index as f32).sin();
-        
-        root.draw(&Text::new(label.to_string(), (x_label as i32, y_label as i32), ("Arial", 15)))?;
+        let data = rows(&["Year", "Region", "Team"], &[&["2024", "north", "a"]]);
+        template.group_column = Some("Year".to_string());
+        assert_eq!(value(&data, &template), Some(vec!["Region".to_string()]));
 
-        radar_points.push((x as i32, y as i32));
+        let data = rows(&["Team", "color"], &[&["a", "red"]]);
+        template.group_column = None;
+        let message = resolve_bar_columns(&data, &template).err().unwrap().to_string();
+        assert!(message.contains("need a category and a value column, but the file only has: Team, color"), "{}", message);
     }
-    radar_points.push(radar_points[0]);
 
-    root.draw(&Polygon::new(radar_points.clone(), RED.mix(0.5).filled()))?;
+    #[test]
+    fn labels_a_single_dated_row_with_the_days_around_it() {
+        let input = scratch_csv("single_date", "when,sales\n2023-10-29,5\n");
+        let output = std::env::temp_dir().join(format!("main_single_date_{}.svg", std::process::id()));
+        let mut template = Template::new(ChartKind::Line);
+        template.inputs.push(input.clone());
+        template.output = output.to_string_lossy().to_string();
+        let result = render_template(&template);
+        let svg = std::fs::read_to_string(&output);
+        let _ = std::fs::remove_file(&output);
+        std::fs::remove_file(&input).unwrap();
+        result.unwrap();
+        let svg = svg.unwrap();
+        for label in ["2023-10-28", "2023-10-29", "2023-10-30"] {
+            assert!(svg.contains(label), "{}", label);
+        }
+    }
 
-    Ok(())
+    #[test]
+    fn refuses_a_format_that_does_not_match_the_file_name() {
+        let input = scratch_csv("format_mismatch", "name,age\nAda,36\n");
+        let output = std::env::temp_dir().join(format!("main_format_mismatch_{}.png", std::process::id()));
+        let mut template = Template::new(ChartKind::Bar);
+        template.inputs.push(input.clone());
+        template.output = output.to_string_lossy().to_string();
+        template.format = Some(OutputFormat::Svg);
+        let error = render_template(&template).unwrap_err();
+        std::fs::remove_file(&input).unwrap();
+        assert!(matches!(error.downcast_ref(), Some(ChartError::Output { .. })), "{}", error);
+        assert!(!output.exists());
+    }
 }
-
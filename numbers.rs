@@ -0,0 +1,173 @@
+// src/numbers.rs
+
+// How value labels write their numbers
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NumberStyle {
+    Plain,
+    // Fractions as percentages: 0.25 is "25%"
+    Percent,
+    // Large numbers with a k, M, G, T or P suffix: 1500 is "1.5k"
+    Si,
+}
+
+impl NumberStyle {
+    pub fn parse(name: &str) -> Option<NumberStyle> {
+        match name.trim().to_lowercase().as_str() {
+            "plain" => Some(NumberStyle::Plain),
+            "percent" | "%" => Some(NumberStyle::Percent),
+            "si" => Some(NumberStyle::Si),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NumberFormat {
+    pub style: NumberStyle,
+    // Fixed number of decimals; without it, up to two are shown and trailing zeros dropped
+    pub decimals: Option<usize>,
+    // Group the digits before the decimal point in threes: 1,234,567
+    pub thousands: bool,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        NumberFormat {
+            style: NumberStyle::Plain,
+            decimals: None,
+            thousands: false,
+        }
+    }
+}
+
+const SI_PREFIXES: [(f64, &str); 5] = [(1e15, "P"), (1e12, "T"), (1e9, "G"), (1e6, "M"), (1e3, "k")];
+
+impl NumberFormat {
+    pub fn format(&self, value: f64) -> String {
+        let (scaled, suffix) = match self.style {
+            NumberStyle::Plain => (value, ""),
+            NumberStyle::Percent => (value * 100.0, "%"),
+            NumberStyle::Si => {
+                // Move up a prefix while the rounded number reaches 1000, so 999,999 is "1M", not "1000k"
+                let mut scaled = (value, "");
+                for (scale, prefix) in SI_PREFIXES.iter().rev() {
+                    if self.round(scaled.0).abs() < 1000.0 {
+                        break;
+                    }
+                    scaled = (value / scale, *prefix);
+                }
+                scaled
+            }
+        };
+
+        let mut text = self.digits(scaled);
+        // Rounding a small negative number can leave "-0"
+        if text.starts_with('-') && text[1..].chars().all(|c| c == '0' || c == '.') {
+            text.remove(0);
+        }
+        if self.thousands {
+            text = group_thousands(&text);
+        }
+        text + suffix
+    }
+
+    fn digits(&self, value: f64) -> String {
+        match self.decimals {
+            Some(decimals) => format!("{:.*}", decimals, value),
+            None => {
+                let text = format!("{:.2}", value);
+                text.trim_end_matches('0').trim_end_matches('.').to_string()
+            }
+        }
+    }
+
+    // The value as it will be shown
+    fn round(&self, value: f64) -> f64 {
+        self.digits(value).parse().unwrap_or(value)
+    }
+}
+
+fn group_thousands(text: &str) -> String {
+    let (sign, digits) = match text.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", text),
+    };
+    let (integer, fraction) = match digits.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (digits, None),
+    };
+
+    let mut grouped = sign.to_string();
+    for (index, digit) in integer.chars().enumerate() {
+        if index > 0 && (integer.len() - index) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    if let Some(fraction) = fraction {
+        grouped.push('.');
+        grouped.push_str(fraction);
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(style: NumberStyle, decimals: Option<usize>, thousands: bool, value: f64) -> String {
+        NumberFormat { style, decimals, thousands }.format(value)
+    }
+
+    #[test]
+    fn drops_trailing_zeros_unless_decimals_are_fixed() {
+        assert_eq!(format(NumberStyle::Plain, None, false, 2.5), "2.5");
+        assert_eq!(format(NumberStyle::Plain, None, false, 3.0), "3");
+        assert_eq!(format(NumberStyle::Plain, None, false, 1.23456), "1.23");
+        assert_eq!(format(NumberStyle::Plain, Some(3), false, 2.5), "2.500");
+        assert_eq!(format(NumberStyle::Plain, Some(0), false, 2.7), "3");
+    }
+
+    #[test]
+    fn writes_fractions_as_percentages() {
+        assert_eq!(format(NumberStyle::Percent, None, false, 0.25), "25%");
+        assert_eq!(format(NumberStyle::Percent, Some(1), false, 0.12345), "12.3%");
+        assert_eq!(format(NumberStyle::Percent, None, true, 12.5), "1,250%");
+    }
+
+    #[test]
+    fn shortens_large_numbers_with_si_prefixes() {
+        assert_eq!(format(NumberStyle::Si, None, false, 1500.0), "1.5k");
+        assert_eq!(format(NumberStyle::Si, None, false, -2_500_000.0), "-2.5M");
+        assert_eq!(format(NumberStyle::Si, None, false, 3e9), "3G");
+        assert_eq!(format(NumberStyle::Si, None, false, 7e18), "7000P");
+        assert_eq!(format(NumberStyle::Si, None, false, 999.0), "999");
+        assert_eq!(format(NumberStyle::Si, None, false, 999.999), "1k");
+        assert_eq!(format(NumberStyle::Si, None, false, 999_999.0), "1M");
+        assert_eq!(format(NumberStyle::Si, Some(4), false, 999_999.0), "999.9990k");
+    }
+
+    #[test]
+    fn groups_thousands_and_keeps_the_sign() {
+        assert_eq!(format(NumberStyle::Plain, None, true, 1_234_567.891), "1,234,567.89");
+        assert_eq!(format(NumberStyle::Plain, None, true, -123_456.0), "-123,456");
+        assert_eq!(format(NumberStyle::Plain, None, true, 999.0), "999");
+        assert_eq!(format(NumberStyle::Si, None, true, 1.234e18), "1,234P");
+    }
+
+    #[test]
+    fn never_writes_negative_zero() {
+        assert_eq!(format(NumberStyle::Plain, None, false, -0.001), "0");
+        assert_eq!(format(NumberStyle::Plain, Some(2), false, -0.0), "0.00");
+        assert_eq!(format(NumberStyle::Percent, Some(1), false, -0.0001), "0.0%");
+        assert_eq!(format(NumberStyle::Plain, None, false, -0.5), "-0.5");
+    }
+
+    #[test]
+    fn parses_style_names() {
+        assert_eq!(NumberStyle::parse(" SI "), Some(NumberStyle::Si));
+        assert_eq!(NumberStyle::parse("%"), Some(NumberStyle::Percent));
+        assert_eq!(NumberStyle::parse("plain"), Some(NumberStyle::Plain));
+        assert_eq!(NumberStyle::parse("money"), None);
+    }
+}
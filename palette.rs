@@ -0,0 +1,391 @@
+// src/palette.rs
+
+use plotters::style::{RGBAColor, RGBColor};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Palette {
+    // Ten well separated hues
+    Categorical,
+    // Okabe-Ito colors, distinguishable with the common kinds of color blindness
+    Colorblind,
+    // Light-to-dark shades for ordered categories
+    Sequential,
+    // Random but readable colors, the same for a name and seed on every run
+    Random,
+}
+
+impl Palette {
+    pub fn parse(name: &str) -> Option<Palette> {
+        match name.trim().to_lowercase().as_str() {
+            "categorical" | "tableau" => Some(Palette::Categorical),
+            "colorblind" | "colorblind-safe" | "okabe-ito" => Some(Palette::Colorblind),
+            "sequential" | "viridis" => Some(Palette::Sequential),
+            "random" => Some(Palette::Random),
+            _ => None,
+        }
+    }
+}
+
+const CATEGORICAL: [RGBColor; 10] = [
+    RGBColor(0x4e, 0x79, 0xa7),
+    RGBColor(0xf2, 0x8e, 0x2b),
+    RGBColor(0xe1, 0x57, 0x59),
+    RGBColor(0x76, 0xb7, 0xb2),
+    RGBColor(0x59, 0xa1, 0x4f),
+    RGBColor(0xed, 0xc9, 0x48),
+    RGBColor(0xb0, 0x7a, 0xa1),
+    RGBColor(0xff, 0x9d, 0xa7),
+    RGBColor(0x9c, 0x75, 0x5f),
+    RGBColor(0xba, 0xb0, 0xac),
+];
+
+const COLORBLIND: [RGBColor; 8] = [
+    RGBColor(0xe6, 0x9f, 0x00),
+    RGBColor(0x56, 0xb4, 0xe9),
+    RGBColor(0x00, 0x9e, 0x73),
+    RGBColor(0xf0, 0xe4, 0x42),
+    RGBColor(0x00, 0x72, 0xb2),
+    RGBColor(0xd5, 0x5e, 0x00),
+    RGBColor(0xcc, 0x79, 0xa7),
+    RGBColor(0x00, 0x00, 0x00),
+];
+
+// Viridis stops, blended for any number of shades
+const SEQUENTIAL: [RGBColor; 5] = [
+    RGBColor(0xfd, 0xe7, 0x25),
+    RGBColor(0x5e, 0xc9, 0x62),
+    RGBColor(0x21, 0x91, 0x8c),
+    RGBColor(0x3b, 0x52, 0x8b),
+    RGBColor(0x44, 0x01, 0x54),
+];
+
+// CSS named colors, sorted by name for binary search
+const CSS_COLORS: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+// `#RRGGBB`, `#RRGGBBAA` or a CSS color name such as "steelblue"
+pub fn parse_color(text: &str) -> Option<RGBAColor> {
+    let text = text.trim();
+    let Some(hex) = text.strip_prefix('#') else {
+        let name = text.to_lowercase();
+        let index = CSS_COLORS.binary_search_by(|(css, _)| css.cmp(&name.as_str())).ok()?;
+        let rgb = CSS_COLORS[index].1;
+        return Some(RGBAColor((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 1.0));
+    };
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    let alpha = if hex.len() == 8 { channel(6)? as f64 / 255.0 } else { 1.0 };
+    Some(RGBAColor(channel(0)?, channel(2)?, channel(4)?, alpha))
+}
+
+// FNV-1a, so a name hashes the same on every run and platform
+fn stable_hash(name: &str, seed: u64) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in seed.to_le_bytes().iter().chain(name.as_bytes()) {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+// The color a fraction `t` of the way along the stops
+fn blend(stops: &[RGBColor], t: f64) -> RGBColor {
+    let position = t.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+    let index = (position.floor() as usize).min(stops.len() - 2);
+    let (from, to) = (stops[index], stops[index + 1]);
+    let fraction = position - index as f64;
+    let channel = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * fraction).round() as u8;
+    RGBColor(channel(from.0, to.0), channel(from.1, to.1), channel(from.2, to.2))
+}
+
+// A random hue at a fixed saturation and brightness, so text and borders stay readable on it
+fn random_color(rng: &mut StdRng) -> RGBColor {
+    let hue = rng.gen_range(0.0..6.0);
+    let (saturation, value) = (0.65, 0.85);
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0_f64).abs());
+    let (r, g, b) = match hue as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let channel = |c: f64| ((c + value - chroma) * 255.0).round() as u8;
+    RGBColor(channel(r), channel(g), channel(b))
+}
+
+// One color per name. Categorical palettes give each name the color its name (and the seed)
+// hashes to, so a category keeps its color across reruns and across charts. Where two names in
+// a chart hash to the same color, the one that sorts first keeps it and the other moves on to
+// the next free one, whatever order the names come in. Sequential palettes shade the names in
+// order, and repeated names always get the same color.
+pub fn assign(palette: Palette, seed: Option<u64>, names: &[&str]) -> Vec<RGBColor> {
+    let seed = seed.unwrap_or(0);
+    let colors: &[RGBColor] = match palette {
+        Palette::Categorical => &CATEGORICAL,
+        Palette::Colorblind => &COLORBLIND,
+        Palette::Sequential => {
+            let mut order: Vec<&str> = Vec::new();
+            for name in names {
+                if !order.contains(name) {
+                    order.push(name);
+                }
+            }
+            let steps = order.len().saturating_sub(1).max(1) as f64;
+            return names
+                .iter()
+                .map(|name| {
+                    let index = order.iter().position(|seen| seen == name).unwrap_or(0);
+                    blend(&SEQUENTIAL, index as f64 / steps)
+                })
+                .collect();
+        }
+        Palette::Random => {
+            return names
+                .iter()
+                .map(|name| random_color(&mut StdRng::seed_from_u64(stable_hash(name, seed))))
+                .collect();
+        }
+    };
+
+    let mut unique = names.to_vec();
+    unique.sort_unstable();
+    unique.dedup();
+    let mut taken = vec![false; colors.len()];
+    let mut assigned: HashMap<&str, RGBColor> = HashMap::new();
+    for name in unique {
+        let start = (stable_hash(name, seed) % colors.len() as u64) as usize;
+        let slot = (0..colors.len())
+            .map(|offset| (start + offset) % colors.len())
+            .find(|slot| !taken[*slot])
+            .unwrap_or(start);
+        taken[slot] = true;
+        assigned.insert(name, colors[slot]);
+    }
+    names.iter().map(|name| assigned[name]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex_and_css_colors() {
+        assert_eq!(parse_color("#4682b4"), Some(RGBAColor(70, 130, 180, 1.0)));
+        assert_eq!(parse_color(" SteelBlue "), Some(RGBAColor(70, 130, 180, 1.0)));
+        assert_eq!(parse_color("#ff000080"), Some(RGBAColor(255, 0, 0, 128.0 / 255.0)));
+        for text in ["#fff", "#gg0000", "4682b4", "notacolor", ""] {
+            assert_eq!(parse_color(text), None, "{}", text);
+        }
+        assert!(CSS_COLORS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn hashes_names_the_same_way_every_run() {
+        assert_eq!(stable_hash("", 0), 0xa8c7_f832_281a_39c5);
+        assert_eq!(stable_hash("north", 7), stable_hash("north", 7));
+        assert_ne!(stable_hash("north", 7), stable_hash("north", 8));
+        assert_ne!(stable_hash("north", 7), stable_hash("south", 7));
+    }
+
+    #[test]
+    fn keeps_a_category_color_while_no_other_name_takes_it() {
+        let alone = assign(Palette::Categorical, Some(3), &["north"]);
+        let mixed = assign(Palette::Categorical, Some(3), &["south", "north", "east"]);
+        assert_eq!(alone[0], mixed[1]);
+        assert_eq!(assign(Palette::Random, None, &["a", "b"]), assign(Palette::Random, None, &["a", "b"]));
+        assert_eq!(assign(Palette::Random, None, &["a"])[0], assign(Palette::Random, None, &["b", "a"])[1]);
+    }
+
+    #[test]
+    fn settles_shared_colors_the_same_way_in_any_order() {
+        let slot = |name: &str| stable_hash(name, 0) % CATEGORICAL.len() as u64;
+        assert_eq!((slot("North"), slot("East")), (0, 0));
+
+        let north_first = assign(Palette::Categorical, None, &["North", "East"]);
+        let east_first = assign(Palette::Categorical, None, &["East", "North"]);
+        assert_eq!(north_first, [east_first[1], east_first[0]]);
+        // "East" sorts first, so it keeps the color both names hash to
+        assert_eq!(east_first[0], CATEGORICAL[0]);
+        assert_ne!(east_first[1], CATEGORICAL[0]);
+    }
+
+    #[test]
+    fn gives_distinct_names_distinct_colors_until_the_palette_runs_out() {
+        let names: Vec<String> = (0..COLORBLIND.len()).map(|i| format!("series {}", i)).collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let colors = assign(Palette::Colorblind, None, &names);
+        for (i, color) in colors.iter().enumerate() {
+            assert!(!colors[..i].contains(color), "{:?} repeats", color);
+        }
+
+        let colors = assign(Palette::Categorical, None, &["a", "b", "a"]);
+        assert_eq!(colors[0], colors[2]);
+        assert_ne!(colors[0], colors[1]);
+    }
+
+    #[test]
+    fn shades_sequential_names_in_first_seen_order() {
+        let colors = assign(Palette::Sequential, None, &["low", "mid", "low", "high"]);
+        assert_eq!(colors[0], SEQUENTIAL[0]);
+        assert_eq!(colors[2], colors[0]);
+        assert_eq!(colors[1], blend(&SEQUENTIAL, 0.5));
+        assert_eq!(colors[3], SEQUENTIAL[SEQUENTIAL.len() - 1]);
+        assert_eq!(assign(Palette::Sequential, None, &["only"]), [SEQUENTIAL[0]]);
+    }
+}
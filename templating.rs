@@ -1,24 +1,1117 @@
 // src/templating.rs
 
-use std::collections::HashMap;
+use crate::charts;
+use crate::datetime;
+use crate::error::ChartError;
+use crate::numbers::{NumberFormat, NumberStyle};
+use crate::palette::{self, Palette};
+use plotters::style::RGBColor;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChartKind {
+    Bar,
+    Scatter,
+    Pie,
+    Line,
+    Radar,
+    Histogram,
+}
+
+impl ChartKind {
+    // Any of the names in the chart registry
+    pub fn parse(name: &str) -> Option<ChartKind> {
+        charts::find(name).map(|entry| entry.kind)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Png,
+    Svg,
+}
+
+impl OutputFormat {
+    pub fn parse(name: &str) -> Option<OutputFormat> {
+        match name.trim().trim_start_matches('.').to_lowercase().as_str() {
+            "png" => Some(OutputFormat::Png),
+            "svg" => Some(OutputFormat::Svg),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Png => "png",
+            OutputFormat::Svg => "svg",
+        }
+    }
+}
+
+// How a line chart with several value columns is drawn
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineMode {
+    Line,
+    Area,
+    Stacked,
+}
+
+impl LineMode {
+    pub fn parse(name: &str) -> Option<LineMode> {
+        match name.trim().to_lowercase().as_str() {
+            "line" | "lines" => Some(LineMode::Line),
+            "area" => Some(LineMode::Area),
+            "stacked" | "stacked-area" => Some(LineMode::Stacked),
+            _ => None,
+        }
+    }
+}
+
+// How a bar chart with several series is drawn
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BarMode {
+    // Series side by side within each category
+    Grouped,
+    Stacked,
+    // Stacked, with every category scaled to 100%
+    Percent,
+}
+
+impl BarMode {
+    pub fn parse(name: &str) -> Option<BarMode> {
+        match name.trim().to_lowercase().as_str() {
+            "grouped" | "clustered" => Some(BarMode::Grouped),
+            "stacked" => Some(BarMode::Stacked),
+            "percent" | "stacked-percent" | "100%" => Some(BarMode::Percent),
+            _ => None,
+        }
+    }
+}
+
+// How a histogram divides its values into bins
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Binning {
+    Count(usize),
+    Width(f64),
+    // ceil(log2(n)) + 1 bins
+    Sturges,
+    // Bins 2 * IQR / cbrt(n) wide, which suits skewed data and outliers
+    FreedmanDiaconis,
+}
+
+impl Binning {
+    // A bin count or the name of a rule
+    pub fn parse(name: &str) -> Option<Binning> {
+        match name.trim().to_lowercase().as_str() {
+            "sturges" => Some(Binning::Sturges),
+            "fd" | "freedman-diaconis" => Some(Binning::FreedmanDiaconis),
+            count => match count.parse::<usize>() {
+                Ok(count) if count > 0 => Some(Binning::Count(count)),
+                _ => None,
+            },
+        }
+    }
+}
+
+// Order of bar chart categories by their value
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+impl SortOrder {
+    pub fn parse(name: &str) -> Option<SortOrder> {
+        match name.trim().to_lowercase().as_str() {
+            "asc" | "ascending" => Some(SortOrder::Ascending),
+            "desc" | "descending" => Some(SortOrder::Descending),
+            _ => None,
+        }
+    }
+}
+
+// Shape of the value rings behind a radar chart
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RadarGrid {
+    Polygon,
+    Circle,
+}
+
+impl RadarGrid {
+    pub fn parse(name: &str) -> Option<RadarGrid> {
+        match name.trim().to_lowercase().as_str() {
+            "polygon" | "polygonal" => Some(RadarGrid::Polygon),
+            "circle" | "circular" => Some(RadarGrid::Circle),
+            _ => None,
+        }
+    }
+}
+
+// Whether every radar axis shares one value scale or is scaled to its own data
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RadarScale {
+    Global,
+    PerAxis,
+}
+
+impl RadarScale {
+    pub fn parse(name: &str) -> Option<RadarScale> {
+        match name.trim().to_lowercase().as_str() {
+            "global" | "shared" => Some(RadarScale::Global),
+            "per-axis" | "per_axis" | "axis" => Some(RadarScale::PerAxis),
+            _ => None,
+        }
+    }
+}
+
+// What each pie slice shows besides its label
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PieAnnotation {
+    None,
+    Percent,
+    Value,
+    Both,
+}
+
+impl PieAnnotation {
+    pub fn parse(name: &str) -> Option<PieAnnotation> {
+        match name.trim().to_lowercase().as_str() {
+            "none" => Some(PieAnnotation::None),
+            "percent" | "percentage" => Some(PieAnnotation::Percent),
+            "value" => Some(PieAnnotation::Value),
+            "both" => Some(PieAnnotation::Both),
+            _ => None,
+        }
+    }
+}
+
+// A fixed value range for one radar axis, named by its label
+#[derive(Clone, Debug)]
+pub struct RadarAxisLimit {
+    pub label: String,
+    pub min: f64,
+    pub max: f64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct AxisSpec {
+    pub label: Option<String>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+#[derive(Clone, Debug)]
 pub struct Template {
-    pub visualization_type: String,
+    pub visualization_type: ChartKind,
+    pub inputs: Vec<String>,
+    pub output: String,
+    pub format: Option<OutputFormat>,
+    pub title: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    // Multiplies the canvas size along with every font, margin and marker, for high-resolution output
+    pub scale: f64,
     pub columns: Vec<String>,
+    pub group_column: Option<String>,
+    pub date_format: Option<String>,
+    pub line_mode: LineMode,
+    pub bar_mode: BarMode,
+    // Bars run left to right, with the categories down the y axis
+    pub horizontal: bool,
+    pub sort: Option<SortOrder>,
+    // Keep the largest categories and add the rest up into an "Other" bar
+    pub top: Option<usize>,
+    pub radar_grid: RadarGrid,
+    pub radar_scale: RadarScale,
+    pub radar_limits: Vec<RadarAxisLimit>,
+    pub pie_annotation: PieAnnotation,
+    // Inner radius of a donut chart as a fraction of the outer radius
+    pub donut: Option<f64>,
+    // Text in the middle of a donut; `{total}` stands for the sum of the values
+    pub center_text: Option<String>,
+    // Labels of the pie slices pulled out from the center
+    pub explode: Vec<String>,
+    // Draw a legend beside charts that label their data directly
+    pub legend: bool,
+    pub binning: Binning,
+    // Draw a smoothed density curve over each histogram distribution
+    pub density: bool,
+    // Print each bar's, line vertex's or scatter point's value beside it
+    pub value_labels: bool,
+    pub number_format: NumberFormat,
+    pub colors: Vec<RGBColor>,
+    pub palette: Option<Palette>,
+    // Varies which color each name gets from the palette, keeping it the same between runs
+    pub seed: Option<u64>,
+    pub x_axis: AxisSpec,
+    pub y_axis: AxisSpec,
+}
+
+impl Template {
+    pub fn new(visualization_type: ChartKind) -> Self {
+        Self {
+            visualization_type,
+            inputs: Vec::new(),
+            output: String::new(),
+            format: None,
+            title: String::new(),
+            width: None,
+            height: None,
+            scale: 1.0,
+            columns: Vec::new(),
+            group_column: None,
+            date_format: None,
+            line_mode: LineMode::Area,
+            bar_mode: BarMode::Grouped,
+            horizontal: false,
+            sort: None,
+            top: None,
+            radar_grid: RadarGrid::Polygon,
+            radar_scale: RadarScale::Global,
+            radar_limits: Vec::new(),
+            pie_annotation: PieAnnotation::Percent,
+            donut: None,
+            center_text: None,
+            explode: Vec::new(),
+            legend: false,
+            binning: Binning::Sturges,
+            density: false,
+            value_labels: false,
+            number_format: NumberFormat::default(),
+            colors: Vec::new(),
+            palette: None,
+            seed: None,
+            x_axis: AxisSpec::default(),
+            y_axis: AxisSpec::default(),
+        }
+    }
+
+    // Canvas size, falling back to the chart's default for any missing dimension
+    pub fn size(&self, default: (u32, u32)) -> (u32, u32) {
+        (
+            self.width.unwrap_or(default.0),
+            self.height.unwrap_or(default.1),
+        )
+    }
+
+    // Canvas size in output pixels, after applying the scale factor
+    pub fn pixel_size(&self, default: (u32, u32)) -> (u32, u32) {
+        let (width, height) = self.size(default);
+        (
+            (width as f64 * self.scale).round().max(1.0) as u32,
+            (height as f64 * self.scale).round().max(1.0) as u32,
+        )
+    }
+
+    // A font size or line width at the template's scale
+    pub fn scaled(&self, size: f64) -> f64 {
+        size * self.scale
+    }
+
+    // A distance in pixels at the template's scale
+    pub fn px(&self, pixels: i32) -> i32 {
+        (pixels as f64 * self.scale).round() as i32
+    }
+
+    // The explicit format if one was given, otherwise the one named by the output file extension
+    pub fn output_format(&self) -> Result<OutputFormat, Box<dyn Error>> {
+        let from_extension = Path::new(&self.output)
+            .extension()
+            .and_then(|extension| OutputFormat::parse(&extension.to_string_lossy()));
+
+        let output_error = |message: String| {
+            Err(ChartError::Output {
+                path: self.output.clone(),
+                message,
+            }
+            .into())
+        };
+        let mismatch = |format: OutputFormat| {
+            let name = format.extension();
+            output_error(format!("{} output needs a file name ending in .{}", name.to_uppercase(), name))
+        };
+        match (self.format, from_extension) {
+            // One format under the other's extension would not open, and the bitmap backend picks
+            // its image encoder from the extension
+            (Some(format), Some(extension)) if extension != format => mismatch(format),
+            (Some(OutputFormat::Png), None) => mismatch(OutputFormat::Png),
+            (Some(format), _) | (None, Some(format)) => Ok(format),
+            (None, None) => output_error("cannot tell the output format. Use a .png or .svg file name".to_string()),
+        }
+    }
+
+    // Sets the drawing mode, whose meaning depends on the chart type
+    pub fn set_mode(&mut self, name: &str) -> Result<(), String> {
+        match self.visualization_type {
+            ChartKind::Line => {
+                self.line_mode = LineMode::parse(name).ok_or_else(|| {
+                    format!("unknown line chart mode '{}'. Expected line, area or stacked", name)
+                })?;
+            }
+            ChartKind::Bar => {
+                self.bar_mode = BarMode::parse(name).ok_or_else(|| {
+                    format!("unknown bar chart mode '{}'. Expected grouped, stacked or percent", name)
+                })?;
+            }
+            _ => return Err(format!("mode '{}' is not supported for this chart type", name)),
+        }
+        Ok(())
+    }
+
+    // One color per series or category name: the spec's colors in order, the named palette,
+    // or the chart's own colors; an empty `default` stands for the categorical palette
+    pub fn colors_for(&self, names: &[&str], default: &[RGBColor]) -> Vec<RGBColor> {
+        let cycle = |colors: &[RGBColor]| (0..names.len()).map(|index| colors[index % colors.len()]).collect();
+        if !self.colors.is_empty() {
+            cycle(&self.colors)
+        } else if let Some(palette) = self.palette {
+            palette::assign(palette, self.seed, names)
+        } else if default.is_empty() {
+            palette::assign(Palette::Categorical, self.seed, names)
+        } else {
+            cycle(default)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TemplateError {
+    pub line: Option<usize>,
+    pub message: String,
 }
 
-pub fn parse_template(template_str: &str) -> Template {
-    // For simplicity, let's assume the template string is "type:table,columns:name,age"
-    let parts: HashMap<_, _> = template_str
-        .split(',')
-        .map(|part| {
-            let kv: Vec<_> = part.split(':').collect();
-            (kv[0].to_string(), kv[1].to_string())
-        })
-        .collect();
+impl TemplateError {
+    fn at(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line: Some(line),
+            message: message.into(),
+        }
+    }
+
+    fn new(message: impl Into<String>) -> Self {
+        Self {
+            line: None,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "Chart spec line {}: {}", line, self.message),
+            None => write!(f, "Chart spec: {}", self.message),
+        }
+    }
+}
+
+impl Error for TemplateError {}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Array(Vec<Value>),
+}
+
+// Reads a chart spec file; relative input and output paths are taken relative to the spec
+pub fn load_template(path: &str) -> Result<Template, Box<dyn Error>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Could not read chart spec '{}': {}", path, e))?;
+    let mut template = parse_template(&contents)?;
+
+    if let Some(dir) = Path::new(path).parent() {
+        let resolve = |file: &str| {
+            if Path::new(file).is_absolute() {
+                file.to_string()
+            } else {
+                dir.join(file).to_string_lossy().into_owned()
+            }
+        };
+        template.inputs = template.inputs.iter().map(|input| resolve(input)).collect();
+        if !template.output.is_empty() {
+            template.output = resolve(&template.output);
+        }
+    }
+
+    Ok(template)
+}
+
+// Parses a TOML chart spec, for example:
+//
+//   type = "bar"
+//   input = "people.csv"
+//   output = "ages.svg"
+//   title = "Ages"
+//   columns = ["name", "age"]
+//   colors = ["#1f77b4", "#ff7f0e"]   # or palette = "colorblind", with an optional seed = 7
+//
+//   [size]
+//   width = 800
+//   height = 600
+//   scale = 2       # or dpi = 192; 96 dpi is a scale of 1
+//
+//   [y_axis]
+//   label = "Age"
+//   min = 0
+//
+// Bar charts plot one series per value column after the category in `columns`, or one per
+// value of a `group` column, with `mode = "grouped"`, `"stacked"` or `"percent"` (stacked to 100%).
+// `orientation = "horizontal"` lists the categories down the y axis, `sort = "descending"` or
+// `"ascending"` orders them by value, and `top = 10` keeps the ten largest plus an "Other" bar.
+// Line charts may also set `date_format = "%d/%m/%Y"` for their x column and
+// `mode = "line"`, `"area"` or `"stacked"` for how their value columns are drawn.
+// Radar charts take `grid = "polygon"` or `"circle"`, `radar_scale = "global"` or `"per-axis"`,
+// the shared value range in [y_axis], and fixed ranges per label in [axis_limits]:
+//
+//   [axis_limits]
+//   "Top speed" = [0, 300]
+//
+// Histograms bin every column in `columns`, or one column split by a `group` column, into
+// `bins = 20`, `"sturges"` or `"fd"` (Freedman-Diaconis) bins, or bins `bin_width = 2.5` wide,
+// and `density = true` draws a smoothed density curve over each distribution.
+// Bar, line and scatter charts print their values with `value_labels = true`, written with
+// `number_style = "plain"`, `"percent"` or `"si"`, `decimals = 1` and `thousands = true`.
+// Pie charts take `annotate = "percent"`, `"value"`, `"both"` or `"none"` and `legend = true`;
+// `donut = 0.5` leaves a hole of half the radius with `center_text = "Total: {total}"` in it,
+// and `explode = ["Apples"]` pulls slices out of the pie.
+pub fn parse_template(template_str: &str) -> Result<Template, TemplateError> {
+    let entries = parse_toml(template_str)?;
+
+    let (_, (kind_value, kind_line)) = entries
+        .iter()
+        .find(|((section, key), _)| section.is_empty() && key == "type")
+        .ok_or_else(|| TemplateError::new("missing required key 'type'"))?;
+    let kind_name = as_string(kind_value, *kind_line, "type")?;
+    let mut template = Template::new(ChartKind::parse(&kind_name).ok_or_else(|| {
+        TemplateError::at(
+            *kind_line,
+            format!("unknown chart type '{}'. Expected one of: {}", kind_name, charts::names()),
+        )
+    })?);
+
+    for ((section, key), (value, line)) in &entries {
+        let line = *line;
+        match (section.as_str(), key.as_str()) {
+            ("", "type") => {}
+            ("", "input") => template.inputs.push(as_string(value, line, key)?),
+            ("", "inputs") => template.inputs.extend(as_string_list(value, line, key)?),
+            ("", "output") => template.output = as_string(value, line, key)?,
+            ("", "format") => {
+                let name = as_string(value, line, key)?;
+                template.format = Some(OutputFormat::parse(&name).ok_or_else(|| {
+                    TemplateError::at(line, format!("unknown format '{}'. Expected 'png' or 'svg'", name))
+                })?)
+            }
+            ("", "title") => template.title = as_string(value, line, key)?,
+            ("", "columns") => template.columns = as_string_list(value, line, key)?,
+            ("", "group") => template.group_column = Some(as_string(value, line, key)?),
+            ("", "mode") => template
+                .set_mode(&as_string(value, line, key)?)
+                .map_err(|e| TemplateError::at(line, e))?,
+            ("", "date_format") => template.date_format = Some(as_string(value, line, key)?),
+            ("", "colors") => {
+                template.colors = as_string_list(value, line, key)?
+                    .iter()
+                    .map(|color| parse_series_color(color).ok_or_else(|| {
+                        TemplateError::at(line, format!("invalid color '{}'. Expected '#RRGGBB' or a CSS color name", color))
+                    }))
+                    .collect::<Result<_, _>>()?
+            }
+            ("", "grid") => {
+                let name = as_string(value, line, key)?;
+                template.radar_grid = RadarGrid::parse(&name).ok_or_else(|| {
+                    TemplateError::at(line, format!("unknown grid '{}'. Expected 'polygon' or 'circle'", name))
+                })?
+            }
+            ("", "radar_scale") => {
+                let name = as_string(value, line, key)?;
+                template.radar_scale = RadarScale::parse(&name).ok_or_else(|| {
+                    TemplateError::at(line, format!("unknown radar scale '{}'. Expected 'global' or 'per-axis'", name))
+                })?
+            }
+            ("", "annotate") => {
+                let name = as_string(value, line, key)?;
+                template.pie_annotation = PieAnnotation::parse(&name).ok_or_else(|| {
+                    TemplateError::at(
+                        line,
+                        format!("unknown annotation '{}'. Expected 'percent', 'value', 'both' or 'none'", name),
+                    )
+                })?
+            }
+            ("", "legend") => template.legend = as_bool(value, line, key)?,
+            ("", "bins") => {
+                template.binning = match value {
+                    Value::Int(count) if *count > 0 => Binning::Count(*count as usize),
+                    Value::Str(name) => Binning::parse(name).ok_or_else(|| {
+                        TemplateError::at(line, format!("unknown binning '{}'. Expected a bin count, 'sturges' or 'fd'", name))
+                    })?,
+                    _ => return Err(TemplateError::at(line, "'bins' must be a positive integer, 'sturges' or 'fd'")),
+                }
+            }
+            ("", "bin_width") => match as_number(value, line, key)? {
+                width if width > 0.0 => template.binning = Binning::Width(width),
+                _ => return Err(TemplateError::at(line, "'bin_width' must be a positive number")),
+            },
+            ("", "density") => template.density = as_bool(value, line, key)?,
+            ("", "value_labels") => template.value_labels = as_bool(value, line, key)?,
+            ("", "number_style") => {
+                let name = as_string(value, line, key)?;
+                template.number_format.style = NumberStyle::parse(&name).ok_or_else(|| {
+                    TemplateError::at(line, format!("unknown number style '{}'. Expected 'plain', 'percent' or 'si'", name))
+                })?
+            }
+            ("", "decimals") => template.number_format.decimals = Some(as_decimals(value, line, key)?),
+            ("", "thousands") => template.number_format.thousands = as_bool(value, line, key)?,
+            ("", "orientation") => {
+                template.horizontal = match as_string(value, line, key)?.trim().to_lowercase().as_str() {
+                    "horizontal" => true,
+                    "vertical" => false,
+                    name => {
+                        return Err(TemplateError::at(
+                            line,
+                            format!("unknown orientation '{}'. Expected 'vertical' or 'horizontal'", name),
+                        ))
+                    }
+                }
+            }
+            ("", "sort") => {
+                let name = as_string(value, line, key)?;
+                template.sort = Some(SortOrder::parse(&name).ok_or_else(|| {
+                    TemplateError::at(line, format!("unknown sort order '{}'. Expected 'ascending' or 'descending'", name))
+                })?)
+            }
+            ("", "top") => template.top = Some(as_dimension(value, line, key)? as usize),
+            ("", "donut") => template.donut = Some(as_fraction(value, line, key)?),
+            ("", "center_text") => template.center_text = Some(as_string(value, line, key)?),
+            ("", "explode") => template.explode = as_string_list(value, line, key)?,
+            ("axis_limits", _) => template.radar_limits.push(as_radar_limit(value, line, key)?),
+            ("", "palette") => {
+                let name = as_string(value, line, key)?;
+                template.palette = Some(Palette::parse(&name).ok_or_else(|| {
+                    TemplateError::at(
+                        line,
+                        format!("unknown palette '{}'. Expected categorical, colorblind, sequential or random", name),
+                    )
+                })?)
+            }
+            ("", "seed") => match value {
+                Value::Int(seed) if *seed >= 0 => template.seed = Some(*seed as u64),
+                _ => return Err(TemplateError::at(line, "'seed' must be a non-negative integer")),
+            },
+            ("size", "width") => template.width = Some(as_dimension(value, line, key)?),
+            ("size", "height") => template.height = Some(as_dimension(value, line, key)?),
+            ("size", "scale") => template.scale = as_scale(value, line, key)?,
+            ("size", "dpi") => template.scale = as_scale(value, line, key)? / 96.0,
+            ("x_axis", _) => set_axis(&mut template.x_axis, key, value, line)?,
+            ("y_axis", _) => set_axis(&mut template.y_axis, key, value, line)?,
+            _ => {
+                let name = if section.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", section, key)
+                };
+                return Err(TemplateError::at(line, format!("unknown key '{}'", name)));
+            }
+        }
+    }
+
+    Ok(template)
+}
+
+fn set_axis(axis: &mut AxisSpec, key: &str, value: &Value, line: usize) -> Result<(), TemplateError> {
+    match key {
+        "label" => axis.label = Some(as_string(value, line, key)?),
+        "min" => axis.min = Some(as_axis_limit(value, line, key)?),
+        "max" => axis.max = Some(as_axis_limit(value, line, key)?),
+        _ => return Err(TemplateError::at(line, format!("unknown axis key '{}'", key))),
+    }
+    Ok(())
+}
+
+// Series colors are opaque, so only `#RRGGBB` and CSS names are accepted
+fn parse_series_color(text: &str) -> Option<RGBColor> {
+    palette::parse_color(text)
+        .filter(|color| color.3 >= 1.0)
+        .map(|color| RGBColor(color.0, color.1, color.2))
+}
+
+fn as_string(value: &Value, line: usize, key: &str) -> Result<String, TemplateError> {
+    match value {
+        Value::Str(s) => Ok(s.clone()),
+        _ => Err(TemplateError::at(line, format!("'{}' must be a string", key))),
+    }
+}
+
+fn as_string_list(value: &Value, line: usize, key: &str) -> Result<Vec<String>, TemplateError> {
+    match value {
+        Value::Array(items) => items.iter().map(|item| as_string(item, line, key)).collect(),
+        _ => Err(TemplateError::at(line, format!("'{}' must be an array of strings", key))),
+    }
+}
+
+fn as_bool(value: &Value, line: usize, key: &str) -> Result<bool, TemplateError> {
+    match value {
+        Value::Bool(b) => Ok(*b),
+        _ => Err(TemplateError::at(line, format!("'{}' must be true or false", key))),
+    }
+}
+
+// TOML allows nan and inf, but no setting has a use for them
+fn as_number(value: &Value, line: usize, key: &str) -> Result<f64, TemplateError> {
+    match value {
+        Value::Int(i) => Ok(*i as f64),
+        Value::Float(f) if f.is_finite() => Ok(*f),
+        Value::Float(_) => Err(TemplateError::at(line, format!("'{}' must be a finite number", key))),
+        _ => Err(TemplateError::at(line, format!("'{}' must be a number", key))),
+    }
+}
+
+// Axis limits are numbers, or ISO-8601 dates for date axes (stored as seconds since the epoch)
+fn as_axis_limit(value: &Value, line: usize, key: &str) -> Result<f64, TemplateError> {
+    match value {
+        Value::Str(s) => datetime::parse_iso8601(s)
+            .map(|timestamp| timestamp as f64)
+            .ok_or_else(|| TemplateError::at(line, format!("'{}' must be a number or an ISO-8601 date", key))),
+        _ => as_number(value, line, key),
+    }
+}
+
+// `label = [min, max]` in the [axis_limits] section
+fn as_radar_limit(value: &Value, line: usize, key: &str) -> Result<RadarAxisLimit, TemplateError> {
+    let (min, max) = match value {
+        Value::Array(items) if items.len() == 2 => (as_number(&items[0], line, key)?, as_number(&items[1], line, key)?),
+        _ => return Err(TemplateError::at(line, format!("'{}' must be an array [min, max]", key))),
+    };
+    if min >= max {
+        return Err(TemplateError::at(line, format!("'{}' minimum must be less than its maximum", key)));
+    }
+    Ok(RadarAxisLimit {
+        label: key.to_string(),
+        min,
+        max,
+    })
+}
+
+fn as_scale(value: &Value, line: usize, key: &str) -> Result<f64, TemplateError> {
+    match as_number(value, line, key)? {
+        scale if scale.is_finite() && scale > 0.0 => Ok(scale),
+        _ => Err(TemplateError::at(line, format!("'{}' must be a positive number", key))),
+    }
+}
+
+fn as_fraction(value: &Value, line: usize, key: &str) -> Result<f64, TemplateError> {
+    match as_number(value, line, key)? {
+        fraction if fraction > 0.0 && fraction < 1.0 => Ok(fraction),
+        _ => Err(TemplateError::at(line, format!("'{}' must be a number between 0 and 1", key))),
+    }
+}
+
+fn as_decimals(value: &Value, line: usize, key: &str) -> Result<usize, TemplateError> {
+    match value {
+        Value::Int(i) if (0..=10).contains(i) => Ok(*i as usize),
+        _ => Err(TemplateError::at(line, format!("'{}' must be an integer from 0 to 10", key))),
+    }
+}
+
+fn as_dimension(value: &Value, line: usize, key: &str) -> Result<u32, TemplateError> {
+    match value {
+        Value::Int(i) if *i > 0 && *i <= u32::MAX as i64 => Ok(*i as u32),
+        _ => Err(TemplateError::at(line, format!("'{}' must be a positive integer", key))),
+    }
+}
+
+// Each value with the line it is on, keyed by (section, key) in file order, so that later keys
+// win where two set the same thing; top-level keys have an empty section
+type Entries = Vec<((String, String), (Value, usize))>;
+
+// Minimal TOML reader: top-level keys, [section] tables, strings, numbers, booleans and arrays
+fn parse_toml(text: &str) -> Result<Entries, TemplateError> {
+    let mut entries: Entries = Vec::new();
+    let mut section = String::new();
+    let mut lines = text.lines().enumerate();
+
+    while let Some((index, raw_line)) = lines.next() {
+        let line_no = index + 1;
+        let line = strip_comment(raw_line);
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with('[') {
+            if !line.ends_with(']') || line.starts_with("[[") {
+                return Err(TemplateError::at(line_no, format!("invalid section header '{}'", line)));
+            }
+            section = line[1..line.len() - 1].trim().to_string();
+            if section.is_empty() {
+                return Err(TemplateError::at(line_no, "empty section name"));
+            }
+            continue;
+        }
+
+        let (key, value_text) = line
+            .split_once('=')
+            .ok_or_else(|| TemplateError::at(line_no, format!("expected 'key = value', found '{}'", line)))?;
+        let key = key.trim().trim_matches('"').to_string();
+        if key.is_empty() {
+            return Err(TemplateError::at(line_no, "missing key before '='"));
+        }
+
+        // Arrays may span several lines until the closing bracket
+        let mut value_text = value_text.trim().to_string();
+        if value_text.starts_with('[') {
+            while bracket_depth(&value_text) > 0 {
+                let (_, next) = lines
+                    .next()
+                    .ok_or_else(|| TemplateError::at(line_no, "unterminated array"))?;
+                value_text.push(' ');
+                value_text.push_str(strip_comment(next).trim());
+            }
+        }
+
+        let mut chars = value_text.trim().chars().peekable();
+        let value = parse_value(&mut chars).map_err(|e| TemplateError::at(line_no, e))?;
+        skip_whitespace(&mut chars);
+        if let Some(extra) = chars.next() {
+            return Err(TemplateError::at(line_no, format!("unexpected '{}' after value", extra)));
+        }
+
+        let name = (section.clone(), key.clone());
+        if entries.iter().any(|(entry, _)| *entry == name) {
+            return Err(TemplateError::at(line_no, format!("duplicate key '{}'", key)));
+        }
+        entries.push((name, (value, line_no)));
+    }
+
+    Ok(entries)
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(q), c) if c == q && !escaped => quote = None,
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '#') => return &line[..i],
+            _ => {}
+        }
+        escaped = false;
+    }
+    line
+}
+
+// Brackets inside strings do not count, including after an escaped quote in a "..." string
+fn bracket_depth(text: &str) -> i32 {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for c in text.chars() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(q), c) if c == q && !escaped => quote = None,
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '[') => depth += 1,
+            (None, ']') => depth -= 1,
+            _ => {}
+        }
+        escaped = false;
+    }
+    depth
+}
+
+fn skip_whitespace(chars: &mut std::iter::Peekable<std::str::Chars>) {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+fn parse_value(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<Value, String> {
+    skip_whitespace(chars);
+    match chars.peek().copied() {
+        None => Err("missing value".to_string()),
+        Some('"') => {
+            chars.next();
+            let mut s = String::new();
+            loop {
+                match chars.next() {
+                    None => return Err("unterminated string".to_string()),
+                    Some('"') => return Ok(Value::Str(s)),
+                    Some('\\') => match chars.next() {
+                        Some('n') => s.push('\n'),
+                        Some('t') => s.push('\t'),
+                        Some('"') => s.push('"'),
+                        Some('\\') => s.push('\\'),
+                        other => return Err(format!("invalid escape '\\{}'", other.unwrap_or(' '))),
+                    },
+                    Some(c) => s.push(c),
+                }
+            }
+        }
+        Some('\'') => {
+            chars.next();
+            let mut s = String::new();
+            loop {
+                match chars.next() {
+                    None => return Err("unterminated string".to_string()),
+                    Some('\'') => return Ok(Value::Str(s)),
+                    Some(c) => s.push(c),
+                }
+            }
+        }
+        Some('[') => {
+            chars.next();
+            let mut items = Vec::new();
+            loop {
+                skip_whitespace(chars);
+                if chars.peek() == Some(&']') {
+                    chars.next();
+                    return Ok(Value::Array(items));
+                }
+                items.push(parse_value(chars)?);
+                skip_whitespace(chars);
+                match chars.next() {
+                    Some(',') => {}
+                    Some(']') => return Ok(Value::Array(items)),
+                    _ => return Err("expected ',' or ']' in array".to_string()),
+                }
+            }
+        }
+        Some(_) => {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c == ',' || c == ']' || c.is_whitespace() {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            let number = word.replace('_', "");
+            match word.as_str() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                _ => {
+                    if let Ok(i) = number.parse::<i64>() {
+                        Ok(Value::Int(i))
+                    } else if let Ok(f) = number.parse::<f64>() {
+                        Ok(Value::Float(f))
+                    } else {
+                        Err(format!("invalid value '{}'. Strings must be quoted", word))
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(entries: &Entries, section: &str, key: &str) -> Value {
+        let (_, (value, _)) = entries.iter().find(|((s, k), _)| s == section && k == key).unwrap();
+        value.clone()
+    }
+
+    #[test]
+    fn reads_sections_values_and_multiline_arrays() {
+        let entries = parse_toml(
+            "title = 'Ages'  # comment\nwidth = 1_000\n\n[size]\nscale = 1.5\ncolumns = [\n  \"a\",\n  \"b\",\n]\nlegend = true\n",
+        )
+        .unwrap();
+        assert_eq!(entry(&entries, "", "title"), Value::Str("Ages".to_string()));
+        assert_eq!(entry(&entries, "", "width"), Value::Int(1000));
+        assert_eq!(entry(&entries, "size", "scale"), Value::Float(1.5));
+        assert_eq!(
+            entry(&entries, "size", "columns"),
+            Value::Array(vec![Value::Str("a".to_string()), Value::Str("b".to_string())])
+        );
+        let last = (("size".to_string(), "legend".to_string()), (Value::Bool(true), 10));
+        assert_eq!(entries.last(), Some(&last));
+    }
+
+    #[test]
+    fn keeps_escaped_quotes_brackets_and_hashes_inside_strings() {
+        let entries = parse_toml("title = \"a \\\"[\\\" b # c\"\ncolumns = [\"x \\\"]\",\n  'y[']\n").unwrap();
+        assert_eq!(entry(&entries, "", "title"), Value::Str("a \"[\" b # c".to_string()));
+        assert_eq!(
+            entry(&entries, "", "columns"),
+            Value::Array(vec![Value::Str("x \"]".to_string()), Value::Str("y[".to_string())])
+        );
+    }
+
+    #[test]
+    fn reports_the_line_of_a_mistake() {
+        let line_of = |text: &str| parse_toml(text).unwrap_err().line;
+        assert_eq!(line_of("a = 1\na = 2\n"), Some(2));
+        assert_eq!(line_of("a = 1\n\nb = [1, 2\n"), Some(3));
+        assert_eq!(line_of("a = \"open\n"), Some(1));
+        assert_eq!(line_of("a = bare\n"), Some(1));
+        assert_eq!(line_of("[]\n"), Some(1));
+        assert_eq!(line_of("a = 1 2\n"), Some(1));
+    }
+
+    #[test]
+    fn builds_a_template() {
+        let template = parse_template(
+            "type = \"bar\"\ninput = \"in.csv\"\ncolumns = [\"name\", \"age\"]\ncolors = [\"#FF8000\"]\n[size]\nwidth = 800\n[y_axis]\nmin = 0\n",
+        )
+        .unwrap();
+        assert_eq!(template.visualization_type, ChartKind::Bar);
+        assert_eq!(template.inputs, vec!["in.csv".to_string()]);
+        assert_eq!(template.columns, vec!["name".to_string(), "age".to_string()]);
+        assert_eq!(template.colors, vec![RGBColor(255, 128, 0)]);
+        assert_eq!(template.size((640, 480)), (800, 480));
+        assert_eq!(template.y_axis.min, Some(0.0));
+
+        assert!(parse_template("input = \"in.csv\"\n").is_err());
+        assert_eq!(parse_template("type = \"gauge\"\n").unwrap_err().line, Some(1));
+        assert_eq!(parse_template("type = \"bar\"\ncolour = \"red\"\n").unwrap_err().line, Some(2));
+    }
+
+    #[test]
+    fn reads_dated_axis_limits() {
+        let template = parse_template("type = \"line\"\ndate_format = \"%d/%m/%Y\"\n[x_axis]\nmin = \"2023-10-29\"\nmax = 1e9\n").unwrap();
+        assert_eq!(template.date_format.as_deref(), Some("%d/%m/%Y"));
+        assert_eq!(template.x_axis.min, Some(datetime::days_from_civil(2023, 10, 29) as f64 * 86_400.0));
+        assert_eq!(template.x_axis.max, Some(1e9));
+        assert_eq!(parse_template("type = \"line\"\n[x_axis]\nmin = \"soon\"\n").unwrap_err().line, Some(3));
+    }
+
+    #[test]
+    fn sets_modes_only_where_the_chart_has_them() {
+        assert_eq!(parse_template("type = \"line\"\nmode = \"Stacked\"\n").unwrap().line_mode, LineMode::Stacked);
+        assert_eq!(parse_template("type = \"line\"\n").unwrap().line_mode, LineMode::Area);
+        assert_eq!(parse_template("type = \"line\"\nmode = \"pie\"\n").unwrap_err().line, Some(2));
+        assert_eq!(parse_template("type = \"bar\"\nmode = \"100%\"\n").unwrap().bar_mode, BarMode::Percent);
+        assert_eq!(parse_template("type = \"bar\"\n").unwrap().bar_mode, BarMode::Grouped);
+        assert!(Template::new(ChartKind::Bar).set_mode("line").is_err());
+        assert!(Template::new(ChartKind::Pie).set_mode("stacked").is_err());
+    }
+
+    #[test]
+    fn reads_the_bar_order_and_orientation() {
+        let template = parse_template("type = \"bar\"\norientation = \"Horizontal\"\nsort = \"ascending\"\ntop = 3\n").unwrap();
+        assert!(template.horizontal);
+        assert_eq!((template.sort, template.top), (Some(SortOrder::Ascending), Some(3)));
+        assert_eq!(parse_template("type = \"bar\"\norientation = \"diagonal\"\n").unwrap_err().line, Some(2));
+        assert_eq!(parse_template("type = \"bar\"\nsort = \"random\"\n").unwrap_err().line, Some(2));
+    }
+
+    #[test]
+    fn reads_the_value_label_format() {
+        let template = parse_template("type = \"line\"\nvalue_labels = true\nnumber_style = \"percent\"\ndecimals = 0\n").unwrap();
+        assert!(template.value_labels);
+        assert_eq!((template.number_format.style, template.number_format.decimals), (NumberStyle::Percent, Some(0)));
+        assert!(!template.number_format.thousands);
+        assert_eq!(parse_template("type = \"bar\"\ndecimals = -1\n").unwrap_err().line, Some(2));
+        assert_eq!(parse_template("type = \"bar\"\nnumber_style = \"hex\"\n").unwrap_err().line, Some(2));
+    }
+
+    #[test]
+    fn reads_the_histogram_bins() {
+        assert_eq!(parse_template("type = \"histogram\"\nbins = 8\n").unwrap().binning, Binning::Count(8));
+        assert_eq!(parse_template("type = \"histogram\"\nbins = \"FD\"\n").unwrap().binning, Binning::FreedmanDiaconis);
+        let template = parse_template("type = \"histogram\"\nbin_width = 0.5\ndensity = true\n").unwrap();
+        assert_eq!(template.binning, Binning::Width(0.5));
+        assert!(template.density);
+        assert_eq!(parse_template("type = \"histogram\"\nbins = 0\n").unwrap_err().line, Some(2));
+        assert_eq!(parse_template("type = \"histogram\"\nbin_width = 0\n").unwrap_err().line, Some(2));
+    }
+
+    #[test]
+    fn keeps_the_radar_scale_apart_from_the_canvas_scale() {
+        let template = parse_template("type = \"radar\"\nradar_scale = \"per-axis\"\n[size]\nscale = 2\n").unwrap();
+        assert_eq!(template.radar_scale, RadarScale::PerAxis);
+        assert_eq!(template.scale, 2.0);
+        assert_eq!(parse_template("type = \"radar\"\nscale = \"per-axis\"\n").unwrap_err().line, Some(2));
+        assert_eq!(parse_template("type = \"radar\"\nradar_scale = \"wide\"\n").unwrap_err().line, Some(2));
+    }
+
+    #[test]
+    fn reads_the_pie_options() {
+        let template = parse_template("type = \"pie\"\nannotate = \"value\"\nlegend = true\n").unwrap();
+        assert_eq!(template.pie_annotation, PieAnnotation::Value);
+        assert!(template.legend);
+        assert_eq!(parse_template("type = \"pie\"\nlegend = \"yes\"\n").unwrap_err().line, Some(2));
+
+        let template = parse_template("type = \"pie\"\ndonut = 0.25\nexplode = [\"a\"]\n").unwrap();
+        assert_eq!((template.donut, template.explode), (Some(0.25), vec!["a".to_string()]));
+        assert_eq!(parse_template("type = \"pie\"\ndonut = 1\n").unwrap_err().line, Some(2));
+    }
+
+    #[test]
+    fn colors_names_from_the_spec_then_the_palette_then_the_chart() {
+        let mut template = parse_template("type = \"bar\"\npalette = \"colorblind\"\nseed = 3\n").unwrap();
+        assert_eq!(template.colors_for(&["a", "b"], &[]), palette::assign(Palette::Colorblind, Some(3), &["a", "b"]));
+
+        template.palette = None;
+        let chart_colors = [RGBColor(1, 2, 3)];
+        assert_eq!(template.colors_for(&["a", "b"], &chart_colors), [chart_colors[0], chart_colors[0]]);
+        assert_eq!(template.colors_for(&["a"], &[]), palette::assign(Palette::Categorical, Some(3), &["a"]));
+
+        template.colors = vec![RGBColor(9, 9, 9), RGBColor(8, 8, 8)];
+        assert_eq!(template.colors_for(&["a", "b", "c"], &chart_colors)[2], RGBColor(9, 9, 9));
+    }
+
+    #[test]
+    fn picks_the_output_format_from_the_flag_or_the_extension() {
+        let format = |output: &str, format: Option<OutputFormat>| {
+            let mut template = Template::new(ChartKind::Bar);
+            template.output = output.to_string();
+            template.format = format;
+            template.output_format()
+        };
+        assert_eq!(format("chart.svg", None).unwrap(), OutputFormat::Svg);
+        assert_eq!(format("out/chart.PNG", None).unwrap(), OutputFormat::Png);
+        assert_eq!(format("chart.png", Some(OutputFormat::Png)).unwrap(), OutputFormat::Png);
+        assert!(format("chart", None).is_err());
+        assert!(format("chart.jpg", None).is_err());
+        assert!(format("chart.svg", Some(OutputFormat::Png)).is_err());
+        assert_eq!(format("chart", Some(OutputFormat::Svg)).unwrap(), OutputFormat::Svg);
+        let error = format("chart.png", Some(OutputFormat::Svg)).unwrap_err();
+        assert!(matches!(error.downcast_ref(), Some(ChartError::Output { .. })), "{}", error);
+        assert_eq!(error.to_string(), "Cannot write 'chart.png': SVG output needs a file name ending in .svg");
+        assert_eq!(OutputFormat::parse(".SVG"), Some(OutputFormat::Svg));
+    }
+
+    #[test]
+    fn rejects_non_finite_axis_limits() {
+        for limit in ["nan", "inf", "-inf"] {
+            let error = parse_template(&format!("type = \"scatter\"\n[x_axis]\nmax = {}\n", limit)).unwrap_err();
+            assert_eq!(error.line, Some(3));
+            assert!(error.message.contains("finite"), "{}", error.message);
+        }
+    }
+
+    #[test]
+    fn applies_keys_that_set_the_same_thing_in_file_order() {
+        let template = parse_template("type = \"histogram\"\nbins = 8\nbin_width = 2\n").unwrap();
+        assert_eq!(template.binning, Binning::Width(2.0));
+        let template = parse_template("type = \"histogram\"\nbin_width = 2\nbins = 8\n").unwrap();
+        assert_eq!(template.binning, Binning::Count(8));
+
+        let template = parse_template("type = \"bar\"\n[size]\nscale = 3\ndpi = 192\n").unwrap();
+        assert_eq!(template.scale, 2.0);
+        let template = parse_template("type = \"bar\"\n[size]\ndpi = 192\nscale = 3\n").unwrap();
+        assert_eq!(template.scale, 3.0);
 
-    Template {
-        visualization_type: parts["type"].clone(),
-        columns: parts["columns"].split('|').map(|s| s.to_string()).collect(),
+        let template = parse_template("type = \"scatter\"\ninputs = [\"b.csv\", \"c.csv\"]\ninput = \"a.csv\"\n").unwrap();
+        assert_eq!(template.inputs, ["b.csv", "c.csv", "a.csv"]);
+        let template = parse_template("type = \"radar\"\n[axis_limits]\nz = [0, 1]\na = [0, 2]\nm = [0, 3]\n").unwrap();
+        let labels: Vec<&str> = template.radar_limits.iter().map(|limit| limit.label.as_str()).collect();
+        assert_eq!(labels, ["z", "a", "m"]);
     }
 }